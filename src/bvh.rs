@@ -0,0 +1,270 @@
+//! <https://jacco.ompf2.com/2022/04/13/how-to-build-a-bvh-part-1-basics/>
+//! <https://www.sci.utah.edu/~wald/Publications/2007/ParallelBVHBuild/fastbuild.pdf>
+//! <https://pbr-book.org/3ed-2018/Primitives_and_Intersection_Acceleration/Bounding_Volume_Hierarchies>
+//!
+
+use crate::la::Aabb;
+use crate::la::Vector3;
+
+const BINS: usize = 16;
+const MAX_LEAF: usize = 4;
+const MAX_LEAF_UNSPLIT: usize = 32;
+
+/// 32 bytes, nodes are stored depth first so the first child of an interior node directly follows it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BvhNode {
+    pub bounds: Aabb,
+    /// First index into `Bvh::indices` for leaves, second child for interior nodes.
+    pub offset: u32,
+    /// Zero for interior nodes.
+    pub count: u16,
+    /// Split axis of interior nodes, the first child holds the lower centroids.
+    pub axis: u8,
+}
+
+impl BvhNode {
+    pub fn is_leaf(&self) -> bool {
+        0 < self.count
+    }
+}
+
+/// Hierarchy over primitives given by their bounds, the primitives themselves are tested through callbacks.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    pub nodes: Vec<BvhNode>,
+    /// Primitive indices referenced by the leaves.
+    pub indices: Vec<u32>,
+}
+
+impl Bvh {
+    /// Top down binned surface area heuristic build.
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(bounds.len().max(1) * 2),
+            indices: (0..bounds.len() as u32).collect(),
+        };
+
+        if !bounds.is_empty() {
+            let centroids: Vec<Vector3<f32>> = bounds.iter().map(Aabb::center).collect();
+            bvh.build_node(bounds, &centroids, 0, bounds.len());
+        }
+
+        bvh
+    }
+
+    pub fn from_triangles(triangles: &[[Vector3<f32>; 3]]) -> Self {
+        Self::build(&triangles.iter().map(|triangle| Aabb::from_points(triangle)).collect::<Vec<_>>())
+    }
+
+    /// Recomputes the node bounds bottom up for moved primitives, the topology stays the same so quality degrades with large motion.
+    pub fn refit(&mut self, bounds: &[Aabb]) {
+        for i in (0..self.nodes.len()).rev() {
+            let node = self.nodes[i];
+
+            self.nodes[i].bounds = if node.is_leaf() {
+                self.leaf(&node).iter().fold(Aabb::default(), |b, &index| b.union(&bounds[index as usize]))
+            } else {
+                self.nodes[i + 1].bounds.union(&self.nodes[node.offset as usize].bounds)
+            };
+        }
+    }
+
+    pub fn refit_triangles(&mut self, triangles: &[[Vector3<f32>; 3]]) {
+        self.refit(&triangles.iter().map(|triangle| Aabb::from_points(triangle)).collect::<Vec<_>>());
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::default(), |node| node.bounds)
+    }
+
+    /// Closest hit along the ray, `intersect(primitive, t_max)` returns the distance of a hit closer than `t_max`.
+    pub fn closest_hit<F: FnMut(usize, f32) -> Option<f32>>(&self, origin: Vector3<f32>, direction: Vector3<f32>, t_max: f32, mut intersect: F) -> Option<(usize, f32)> {
+        let mut closest: Option<(usize, f32)> = None;
+        let mut t_max = t_max;
+
+        self.traverse(origin, direction, t_max, |leaf| {
+            for &index in leaf {
+                if let Some(t) = intersect(index as usize, t_max) {
+                    if t < t_max {
+                        t_max = t;
+                        closest = Some((index as usize, t));
+                    }
+                }
+            }
+            (false, t_max)
+        });
+
+        closest
+    }
+
+    /// Stops at the first primitive for which `intersect(primitive, t_max)` reports a hit.
+    pub fn any_hit<F: FnMut(usize, f32) -> bool>(&self, origin: Vector3<f32>, direction: Vector3<f32>, t_max: f32, mut intersect: F) -> bool {
+        let mut hit = false;
+
+        self.traverse(origin, direction, t_max, |leaf| {
+            hit = leaf.iter().any(|&index| intersect(index as usize, t_max));
+            (hit, t_max)
+        });
+
+        hit
+    }
+
+    /// Calls `f` for every primitive whose leaf overlaps `bounds`, for picking and collision queries.
+    pub fn overlapping<F: FnMut(usize)>(&self, bounds: &Aabb, mut f: F) {
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !node.bounds.overlaps(bounds) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                self.leaf(node).iter().for_each(|&index| f(index as usize));
+            } else {
+                stack.push(node.offset as usize);
+                stack.push(i + 1);
+            }
+        }
+    }
+
+    /// Front to back over the leaves the ray enters, `visit` returns whether to stop and the current maximum distance.
+    fn traverse<F: FnMut(&[u32]) -> (bool, f32)>(&self, origin: Vector3<f32>, direction: Vector3<f32>, t_max: f32, mut visit: F) {
+        let Some(root) = self.nodes.first() else {
+            return;
+        };
+
+        let inverse: Vector3<f32> = [1.0 / direction[0][0], 1.0 / direction[0][1], 1.0 / direction[0][2]].into();
+        let mut t_max = t_max;
+
+        if root.bounds.intersect_ray(origin, inverse, t_max).is_none() {
+            return;
+        }
+
+        let mut stack: Vec<(usize, f32)> = Vec::with_capacity(64);
+        stack.push((0, 0.0));
+
+        while let Some((i, t)) = stack.pop() {
+            if t > t_max {
+                continue;
+            }
+
+            let node = &self.nodes[i];
+
+            if node.is_leaf() {
+                let (stop, t) = visit(self.leaf(node));
+                if stop {
+                    return;
+                }
+                t_max = t;
+                continue;
+            }
+
+            let (mut near, mut far) = (i + 1, node.offset as usize);
+            if 0.0 > direction[0][node.axis as usize] {
+                (near, far) = (far, near);
+            }
+
+            let t_near = self.nodes[near].bounds.intersect_ray(origin, inverse, t_max);
+            let t_far = self.nodes[far].bounds.intersect_ray(origin, inverse, t_max);
+
+            if let Some(t) = t_far {
+                stack.push((far, t));
+            }
+            if let Some(t) = t_near {
+                stack.push((near, t));
+            }
+        }
+    }
+
+    fn leaf(&self, node: &BvhNode) -> &[u32] {
+        &self.indices[node.offset as usize..node.offset as usize + node.count as usize]
+    }
+
+    fn build_node(&mut self, bounds: &[Aabb], centroids: &[Vector3<f32>], start: usize, end: usize) {
+        let index = self.nodes.len();
+        let node_bounds = self.indices[start..end].iter().fold(Aabb::default(), |b, &i| b.union(&bounds[i as usize]));
+
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            offset: start as u32,
+            count: (end - start) as u16,
+            axis: 0,
+        });
+
+        let count = end - start;
+        if MAX_LEAF >= count {
+            return;
+        }
+
+        let centroid_bounds = Aabb::from_points(&self.indices[start..end].iter().map(|&i| centroids[i as usize]).collect::<Vec<_>>());
+        let axis = centroid_bounds.largest_axis();
+        let (lo, hi) = (centroid_bounds.min[0][axis], centroid_bounds.max[0][axis]);
+
+        let mid = if 1e-12 >= hi - lo {
+            if MAX_LEAF_UNSPLIT >= count {
+                return;
+            }
+            start + count / 2
+        } else {
+            let bin_of = |i: u32| (((centroids[i as usize][0][axis] - lo) / (hi - lo) * BINS as f32) as usize).min(BINS - 1);
+
+            let mut bins = [(Aabb::default(), 0usize); BINS];
+            for &i in &self.indices[start..end] {
+                let bin = &mut bins[bin_of(i)];
+                bin.0 = bin.0.union(&bounds[i as usize]);
+                bin.1 += 1;
+            }
+
+            let mut right_costs = [0.0f32; BINS];
+            let mut accumulated = (Aabb::default(), 0usize);
+            for split in (1..BINS).rev() {
+                accumulated = (accumulated.0.union(&bins[split].0), accumulated.1 + bins[split].1);
+                right_costs[split] = accumulated.0.surface_area() * accumulated.1 as f32;
+            }
+
+            let mut best = (f32::INFINITY, 0usize);
+            let mut accumulated = (Aabb::default(), 0usize);
+            for split in 1..BINS {
+                accumulated = (accumulated.0.union(&bins[split - 1].0), accumulated.1 + bins[split - 1].1);
+                let cost = accumulated.0.surface_area() * accumulated.1 as f32 + right_costs[split];
+                if cost < best.0 {
+                    best = (cost, split);
+                }
+            }
+
+            let leaf_cost = node_bounds.surface_area() * count as f32;
+            if best.0 + node_bounds.surface_area() >= leaf_cost && MAX_LEAF_UNSPLIT >= count {
+                return;
+            }
+
+            let (mut l, mut r) = (start, end);
+            while l < r {
+                if bin_of(self.indices[l]) < best.1 {
+                    l += 1;
+                } else {
+                    r -= 1;
+                    self.indices.swap(l, r);
+                }
+            }
+
+            if l == start || l == end {
+                self.indices[start..end].sort_by(|&a, &b| centroids[a as usize][0][axis].total_cmp(&centroids[b as usize][0][axis]));
+                start + count / 2
+            } else {
+                l
+            }
+        };
+
+        self.build_node(bounds, centroids, start, mid);
+        let right = self.nodes.len();
+        self.build_node(bounds, centroids, mid, end);
+
+        self.nodes[index].offset = right as u32;
+        self.nodes[index].count = 0;
+        self.nodes[index].axis = axis as u8;
+    }
+}
@@ -1,7 +1,13 @@
+//! <https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm>
+//! <https://en.wikipedia.org/wiki/Nonzero-rule>
 //!
-//!
-//!
 
+use std::f32::consts::PI;
+
+use crate::la::Dot;
+use crate::la::Magnitude;
+use crate::la::Normal;
+use crate::la::Scale;
 use crate::la::Vector2;
 use crate::la::X;
 use crate::la::Y;
@@ -47,3 +53,545 @@ pub fn bresenham<F: FnMut(i32, i32)>(mut p0: Vector2<i32>, mut p1: Vector2<i32>,
         x += 1;
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    EvenOdd,
+    NonZero,
+}
+
+impl FillRule {
+    pub fn inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::EvenOdd => winding & 1 != 0,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PathCommand {
+    MoveTo(Vector2<f32>),
+    LineTo(Vector2<f32>),
+    QuadTo(Vector2<f32>, Vector2<f32>),
+    CubicTo(Vector2<f32>, Vector2<f32>, Vector2<f32>),
+    Close,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Polyline {
+    pub points: Vec<Vector2<f32>>,
+    pub closed: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Path {
+    pub commands: Vec<PathCommand>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, p: Vector2<f32>) {
+        self.commands.push(PathCommand::MoveTo(p));
+    }
+
+    pub fn line_to(&mut self, p: Vector2<f32>) {
+        self.commands.push(PathCommand::LineTo(p));
+    }
+
+    pub fn quad_to(&mut self, c: Vector2<f32>, p: Vector2<f32>) {
+        self.commands.push(PathCommand::QuadTo(c, p));
+    }
+
+    pub fn cubic_to(&mut self, c0: Vector2<f32>, c1: Vector2<f32>, p: Vector2<f32>) {
+        self.commands.push(PathCommand::CubicTo(c0, c1, p));
+    }
+
+    pub fn close(&mut self) {
+        self.commands.push(PathCommand::Close);
+    }
+
+    pub fn polygon(points: &[Vector2<f32>]) -> Self {
+        let mut path = Self::new();
+
+        for (i, p) in points.iter().enumerate() {
+            if 0 == i {
+                path.move_to(*p);
+            } else {
+                path.line_to(*p);
+            }
+        }
+
+        path.close();
+
+        path
+    }
+
+    pub fn append(&mut self, other: &Path) {
+        self.commands.extend_from_slice(&other.commands);
+    }
+
+    /// Splits the path into polylines, curves are subdivided until they deviate from their chords by less than `tolerance`.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Polyline> {
+        let mut polylines: Vec<Polyline> = Vec::new();
+        let mut current = Polyline::default();
+
+        let mut last: Vector2<f32> = [0.0, 0.0].into();
+
+        for command in self.commands.iter() {
+            match *command {
+                PathCommand::MoveTo(p) => {
+                    if 1 < current.points.len() {
+                        polylines.push(current);
+                    }
+                    current = Polyline { points: vec![p], closed: false };
+                    last = p;
+                }
+                PathCommand::LineTo(p) => {
+                    if current.points.is_empty() {
+                        current.points.push(last);
+                    }
+                    current.points.push(p);
+                    last = p;
+                }
+                PathCommand::QuadTo(c, p) => {
+                    if current.points.is_empty() {
+                        current.points.push(last);
+                    }
+                    let dd = (last - c.scale(2.0) + p).magnitude();
+                    let n = ((dd / (4.0 * tolerance)).sqrt().ceil() as usize).clamp(1, 256);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        current.points.push(last.scale(mt * mt) + c.scale(2.0 * mt * t) + p.scale(t * t));
+                    }
+                    last = p;
+                }
+                PathCommand::CubicTo(c0, c1, p) => {
+                    if current.points.is_empty() {
+                        current.points.push(last);
+                    }
+                    let dd = (last - c0.scale(2.0) + c1).magnitude().max((c0 - c1.scale(2.0) + p).magnitude());
+                    let n = ((3.0 * dd / (4.0 * tolerance)).sqrt().ceil() as usize).clamp(1, 256);
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        current.points.push(last.scale(mt * mt * mt) + c0.scale(3.0 * mt * mt * t) + c1.scale(3.0 * mt * t * t) + p.scale(t * t * t));
+                    }
+                    last = p;
+                }
+                PathCommand::Close => {
+                    if let Some(first) = current.points.first().copied() {
+                        current.closed = true;
+                        last = first;
+                        if 1 < current.points.len() {
+                            polylines.push(current);
+                        }
+                        current = Polyline { points: vec![first], closed: false };
+                    }
+                }
+            }
+        }
+
+        if 1 < current.points.len() {
+            polylines.push(current);
+        }
+
+        polylines
+    }
+}
+
+const FILL_SUBSAMPLES: usize = 16;
+const FILL_TOLERANCE: f32 = 0.1;
+
+struct Edge {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    dir: i32,
+}
+
+/// Fills `path` with anti-aliased edges, `f` receives every touched pixel inside `clip` together with its coverage in `(0, 1]`.
+pub fn fill_path<F: FnMut(i32, i32, f32)>(path: &Path, rule: FillRule, clip: (i32, i32, i32, i32), f: F) {
+    let polylines = path.flatten(FILL_TOLERANCE);
+    let contours: Vec<&[Vector2<f32>]> = polylines.iter().map(|p| p.points.as_slice()).collect();
+
+    fill_contours(&contours, rule, clip, f);
+}
+
+/// Fills implicitly closed contours, pixel `(x, y)` covers the square from `(x, y)` to `(x + 1, y + 1)`.
+/// Only pixels inside `clip`, `(x0, y0, x1, y1)` with exclusive upper bounds, are visited.
+pub fn fill_contours<F: FnMut(i32, i32, f32)>(contours: &[&[Vector2<f32>]], rule: FillRule, clip: (i32, i32, i32, i32), mut f: F) {
+    let mut edges: Vec<Edge> = Vec::new();
+
+    let mut min_x = f32::MAX;
+    let mut max_x = f32::MIN;
+
+    for contour in contours {
+        for i in 0..contour.len() {
+            let p0 = contour[i];
+            let p1 = contour[(i + 1) % contour.len()];
+
+            if !(p0.x().is_finite() && p0.y().is_finite() && p1.x().is_finite() && p1.y().is_finite()) {
+                continue;
+            }
+
+            min_x = min_x.min(p0.x());
+            max_x = max_x.max(p0.x());
+
+            if p0.y() == p1.y() {
+                continue;
+            }
+
+            if p0.y() < p1.y() {
+                edges.push(Edge { x0: p0.x(), y0: p0.y(), x1: p1.x(), y1: p1.y(), dir: 1 });
+            } else {
+                edges.push(Edge { x0: p1.x(), y0: p1.y(), x1: p0.x(), y1: p0.y(), dir: -1 });
+            }
+        }
+    }
+
+    if edges.is_empty() {
+        return;
+    }
+
+    edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+
+    let min_y = (edges[0].y0.floor() as i32).max(clip.1);
+    let max_y = (edges.iter().map(|e| e.y1).fold(f32::MIN, f32::max).ceil() as i32).min(clip.3);
+
+    let left = (min_x.floor() as i32).max(clip.0);
+    let right = (max_x.ceil() as i32).min(clip.2);
+    if left >= right || min_y >= max_y {
+        return;
+    }
+
+    let width = (right - left + 2) as usize;
+
+    let mut coverages = vec![0f32; width];
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+
+    let weight = 1.0 / FILL_SUBSAMPLES as f32;
+
+    for y in min_y..max_y {
+        while next < edges.len() && edges[next].y0 < (y + 1) as f32 {
+            active.push(next);
+            next += 1;
+        }
+        active.retain(|&i| edges[i].y1 > y as f32);
+
+        if active.is_empty() {
+            continue;
+        }
+
+        let mut touched_min = usize::MAX;
+        let mut touched_max = 0;
+
+        for s in 0..FILL_SUBSAMPLES {
+            let sy = y as f32 + (s as f32 + 0.5) * weight;
+
+            crossings.clear();
+            for &i in active.iter() {
+                let e = &edges[i];
+                if e.y0 <= sy && sy < e.y1 {
+                    crossings.push((e.x0 + (sy - e.y0) * (e.x1 - e.x0) / (e.y1 - e.y0), e.dir));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            let mut start = 0.0;
+
+            for &(x, dir) in crossings.iter() {
+                let was_inside = rule.inside(winding);
+                winding += dir;
+                let is_inside = rule.inside(winding);
+
+                if !was_inside && is_inside {
+                    start = x;
+                } else if was_inside && !is_inside {
+                    let a = start - left as f32;
+                    let b = x - left as f32;
+                    accumulate_span(&mut coverages, a, b, weight);
+                    touched_min = touched_min.min(a.max(0.0) as usize);
+                    touched_max = touched_max.max((b.max(0.0) as usize).min(width - 1));
+                }
+            }
+        }
+
+        if touched_min > touched_max {
+            continue;
+        }
+
+        for (i, coverage) in coverages.iter_mut().enumerate().take(touched_max + 1).skip(touched_min) {
+            if 0.0 < *coverage {
+                let x = left + i as i32;
+                if x < clip.2 {
+                    f(x, y, coverage.min(1.0));
+                }
+                *coverage = 0.0;
+            }
+        }
+    }
+}
+
+fn accumulate_span(coverages: &mut [f32], a: f32, b: f32, weight: f32) {
+    let a = a.clamp(0.0, (coverages.len() - 1) as f32);
+    let b = b.clamp(0.0, (coverages.len() - 1) as f32);
+
+    if a >= b {
+        return;
+    }
+
+    let ia = a as usize;
+    let ib = b as usize;
+
+    if ia == ib {
+        coverages[ia] += (b - a) * weight;
+        return;
+    }
+
+    coverages[ia] += (ia as f32 + 1.0 - a) * weight;
+    for coverage in coverages.iter_mut().take(ib).skip(ia + 1) {
+        *coverage += weight;
+    }
+    coverages[ib] += (b - ib as f32) * weight;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stroke {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub miter_limit: f32,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+impl Path {
+    /// Builds the outline of the stroke as a union of counter-clockwise pieces, fill it with `FillRule::NonZero`.
+    pub fn stroke(&self, stroke: &Stroke) -> Path {
+        let mut outline = Path::new();
+
+        let hw = stroke.width * 0.5;
+        if 0.0 >= hw {
+            return outline;
+        }
+
+        for polyline in self.flatten(FILL_TOLERANCE) {
+            let mut points: Vec<Vector2<f32>> = Vec::with_capacity(polyline.points.len());
+            for p in polyline.points {
+                if points.last().is_none_or(|q| 1e-6 < (p - *q).magnitude()) {
+                    points.push(p);
+                }
+            }
+
+            let closed = polyline.closed && 2 < points.len();
+            if closed && 1e-6 >= (points[0] - points[points.len() - 1]).magnitude() {
+                points.pop();
+            }
+
+            if 1 == points.len() {
+                match stroke.cap {
+                    LineCap::Butt => {}
+                    LineCap::Round => push_circle(&mut outline, points[0], hw),
+                    LineCap::Square => push_piece(
+                        &mut outline,
+                        &[
+                            points[0] + Vector2::from([-hw, -hw]),
+                            points[0] + Vector2::from([hw, -hw]),
+                            points[0] + Vector2::from([hw, hw]),
+                            points[0] + Vector2::from([-hw, hw]),
+                        ],
+                    ),
+                }
+                continue;
+            }
+
+            let count = if closed { points.len() } else { points.len() - 1 };
+
+            for i in 0..count {
+                let p0 = points[i];
+                let p1 = points[(i + 1) % points.len()];
+                let n = left_normal(p1 - p0).scale(hw);
+
+                push_piece(&mut outline, &[p0 + n, p0 - n, p1 - n, p1 + n]);
+            }
+
+            let joins = if closed { 0..points.len() } else { 1..points.len() - 1 };
+
+            for i in joins {
+                let prev = points[(i + points.len() - 1) % points.len()];
+                let p = points[i];
+                let next = points[(i + 1) % points.len()];
+
+                push_join(&mut outline, prev, p, next, hw, stroke);
+            }
+
+            if !closed {
+                push_cap(&mut outline, points[1], points[0], hw, stroke.cap);
+                push_cap(&mut outline, points[points.len() - 2], points[points.len() - 1], hw, stroke.cap);
+            }
+        }
+
+        outline
+    }
+}
+
+/// Strokes `path` and fills the outline, `clip` and `f` behave as in `fill_path`.
+pub fn stroke_path<F: FnMut(i32, i32, f32)>(path: &Path, stroke: &Stroke, clip: (i32, i32, i32, i32), f: F) {
+    fill_path(&path.stroke(stroke), FillRule::NonZero, clip, f);
+}
+
+fn left_normal(d: Vector2<f32>) -> Vector2<f32> {
+    Vector2::from([-d.y(), d.x()]).normal()
+}
+
+fn push_piece(outline: &mut Path, points: &[Vector2<f32>]) {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let p0 = points[i];
+        let p1 = points[(i + 1) % points.len()];
+        area += p0.x() * p1.y() - p1.x() * p0.y();
+    }
+
+    if 0.0 > area {
+        let reversed: Vec<Vector2<f32>> = points.iter().rev().copied().collect();
+        outline.append(&Path::polygon(&reversed));
+    } else {
+        outline.append(&Path::polygon(points));
+    }
+}
+
+fn push_circle(outline: &mut Path, center: Vector2<f32>, radius: f32) {
+    let step = if FILL_TOLERANCE < radius { 2.0 * (1.0 - FILL_TOLERANCE / radius).acos() } else { PI / 4.0 };
+    let n = ((2.0 * PI / step).ceil() as usize).max(8);
+
+    let points: Vec<Vector2<f32>> = (0..n)
+        .map(|i| {
+            let a = 2.0 * PI * i as f32 / n as f32;
+            center + Vector2::from([a.cos() * radius, a.sin() * radius])
+        })
+        .collect();
+
+    push_piece(outline, &points);
+}
+
+fn push_join(outline: &mut Path, prev: Vector2<f32>, p: Vector2<f32>, next: Vector2<f32>, hw: f32, stroke: &Stroke) {
+    let d0 = (p - prev).normal();
+    let d1 = (next - p).normal();
+
+    let cross = d0.x() * d1.y() - d0.y() * d1.x();
+    if 1e-6 > cross.abs() && 0.0 < d0.dot(d1) {
+        return;
+    }
+
+    if LineJoin::Round == stroke.join {
+        push_circle(outline, p, hw);
+        return;
+    }
+
+    let side = if 0.0 < cross { -hw } else { hw };
+    let n0 = left_normal(d0).scale(side);
+    let n1 = left_normal(d1).scale(side);
+
+    if LineJoin::Miter == stroke.join {
+        let m = (n0 + n1).normal();
+        let cos = m.dot(n0.normal());
+
+        if 1e-6 < cos && stroke.miter_limit >= 1.0 / cos {
+            push_piece(outline, &[p, p + n0, p + m.scale(hw / cos), p + n1]);
+            return;
+        }
+    }
+
+    push_piece(outline, &[p, p + n0, p + n1]);
+}
+
+fn push_cap(outline: &mut Path, from: Vector2<f32>, end: Vector2<f32>, hw: f32, cap: LineCap) {
+    match cap {
+        LineCap::Butt => {}
+        LineCap::Round => push_circle(outline, end, hw),
+        LineCap::Square => {
+            let d = (end - from).normal().scale(hw);
+            let n = left_normal(d).scale(hw);
+            push_piece(outline, &[end + n, end - n, end - n + d, end + n + d]);
+        }
+    }
+}
+
+/// Scanline fill of a possibly concave polygon, pixels whose centers `(x, y)` lie inside are passed to `f`.
+pub fn fill_polygon<F: FnMut(i32, i32)>(points: &[Vector2<i32>], rule: FillRule, mut f: F) {
+    if 3 > points.len() {
+        return;
+    }
+
+    let min_y = points.iter().map(|&p| p.y()).min().unwrap_or(0);
+    let max_y = points.iter().map(|&p| p.y()).max().unwrap_or(0);
+
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+
+    for y in min_y..max_y {
+        crossings.clear();
+
+        for i in 0..points.len() {
+            let p0 = points[i];
+            let p1 = points[(i + 1) % points.len()];
+
+            let (lo, hi, dir) = if p0.y() < p1.y() { (p0, p1, 1) } else { (p1, p0, -1) };
+
+            if lo.y() <= y && y < hi.y() {
+                let t = (y - lo.y()) as f64 / (hi.y() - lo.y()) as f64;
+                crossings.push((lo.x() as f64 + t * (hi.x() - lo.x()) as f64, dir));
+            }
+        }
+
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        let mut start = 0.0;
+
+        for &(x, dir) in crossings.iter() {
+            let was_inside = rule.inside(winding);
+            winding += dir;
+            let is_inside = rule.inside(winding);
+
+            if !was_inside && is_inside {
+                start = x;
+            } else if was_inside && !is_inside {
+                for px in start.ceil() as i32..x.ceil() as i32 {
+                    f(px, y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! <https://www.khronos.org/opengl/wiki/Cubemap_Texture>
+//! <https://learnopengl.com/Advanced-OpenGL/Cubemaps>
+//!
+
+use std::error::Error;
+
+use crate::gl::embed;
+use crate::gl::Fragment;
+use crate::gl::Shader;
+use crate::img::to_float;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::obj::Obj;
+use crate::pbr::equirect_direction;
+use crate::pbr::equirect_uv;
+use crate::pbr::fresnel_schlick;
+use crate::shader::interpolate;
+use crate::shader::reflect;
+use crate::shader::refract;
+use crate::shader::to_color;
+use crate::shader::to_rgba;
+use crate::shader::transform_normal;
+use crate::shader::transform_point;
+use crate::shader::Camera;
+use crate::shader::Uniforms;
+use crate::tga::TGAColor;
+use crate::tga::TGAImage;
+
+/// Faces in the order +X, -X, +Y, -Y, +Z, -Z.
+pub const CUBE_FACES: usize = 6;
+
+/// Forward, right and up axes of each face after the OpenGL cube map table, right is `s` and up is `-t` since TGA rows
+/// start at the bottom, so the bottom left of a face image is `forward - right - up`.
+const FACE_AXES: [[[f32; 3]; 3]; CUBE_FACES] = [
+    [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]],
+    [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+    [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+    [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+    [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+    [[0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+];
+
+pub struct CubeMap {
+    faces: Vec<TGAImage>,
+}
+
+impl CubeMap {
+    /// Faces must be square and of the same size.
+    pub fn new(faces: [TGAImage; CUBE_FACES]) -> Result<Self, Box<dyn Error>> {
+        let size = faces[0].get_width();
+
+        if 0 == size || faces.iter().any(|face| face.get_width() != size || face.get_height() != size) {
+            return Err("立方体贴图尺寸不一致".into());
+        }
+
+        Ok(Self { faces: faces.into() })
+    }
+
+    /// Resamples an equirectangular panorama into faces of `size` pixels.
+    pub fn from_equirect<I: Image>(panorama: &I, size: u16) -> Self {
+        let faces = (0..CUBE_FACES)
+            .map(|face| {
+                let mut image = TGAImage::new(size, size);
+
+                for y in 0..size {
+                    for x in 0..size {
+                        let uv = [(x as f32 + 0.5) / size as f32, (y as f32 + 0.5) / size as f32].into();
+                        let uv = equirect_uv(face_direction(face, uv));
+                        let c = sample_clamped(panorama, uv, true);
+                        image.set_color([x, y].into(), to_rgba(Vector3::from(c), c[0][3]));
+                    }
+                }
+
+                image
+            })
+            .collect();
+
+        Self { faces }
+    }
+
+    pub fn get_size(&self) -> u16 {
+        self.faces[0].get_width()
+    }
+
+    pub fn get_face(&self, face: usize) -> &TGAImage {
+        &self.faces[face]
+    }
+
+    pub fn get_face_mut(&mut self, face: usize) -> &mut TGAImage {
+        &mut self.faces[face]
+    }
+
+    /// Bilinear lookup along `direction`, channels are scaled to `[0, 1]`.
+    pub fn sample(&self, direction: Vector3<f32>) -> Vector4<f32> {
+        let (face, uv) = face_uv(direction);
+
+        sample_clamped(&self.faces[face], uv, false)
+    }
+
+    pub fn to_equirect(&self, width: u16, height: u16) -> TGAImage {
+        let mut image = TGAImage::new(width, height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let direction = equirect_direction([(x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32].into());
+                let c = self.sample(direction);
+                image.set_color([x, y].into(), to_rgba(Vector3::from(c), c[0][3]));
+            }
+        }
+
+        image
+    }
+}
+
+/// Face index and texture coordinate hit by `direction`.
+pub fn face_uv(direction: Vector3<f32>) -> (usize, Vector2<f32>) {
+    let (x, y, z) = (direction.x(), direction.y(), direction.z());
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+    let face = if ax >= ay && ax >= az {
+        if 0.0 <= x { 0 } else { 1 }
+    } else if ay >= az {
+        if 0.0 <= y { 2 } else { 3 }
+    } else if 0.0 <= z {
+        4
+    } else {
+        5
+    };
+
+    let [forward, right, up] = FACE_AXES[face].map(Vector3::from);
+    let major = direction.dot(forward).max(f32::MIN_POSITIVE);
+
+    (face, [0.5 + 0.5 * direction.dot(right) / major, 0.5 + 0.5 * direction.dot(up) / major].into())
+}
+
+/// Unnormalized direction through `uv` of `face`.
+pub fn face_direction(face: usize, uv: Vector2<f32>) -> Vector3<f32> {
+    let [forward, right, up] = FACE_AXES[face].map(Vector3::from);
+
+    forward + right.scale(uv.x() * 2.0 - 1.0) + up.scale(uv.y() * 2.0 - 1.0)
+}
+
+/// Fills every pixel the depth buffer has not been written to with the cube map seen from `camera`.
+pub fn skybox<I: Image>(image: &mut I, depth: &DepthBuffer, cubemap: &CubeMap, camera: &Camera) {
+    fill_background(image, depth, camera, |direction| {
+        let c = cubemap.sample(direction);
+        to_rgba(Vector3::from(c), c[0][3])
+    });
+}
+
+/// Sets every pixel the depth buffer has not been written to to `f` of the unnormalized world direction through it.
+pub fn fill_background<I: Image, F: FnMut(Vector3<f32>) -> TGAColor>(image: &mut I, depth: &DepthBuffer, camera: &Camera, mut f: F) {
+    let width = image.get_width();
+    let height = image.get_height();
+
+    let forward = (camera.center - camera.eye).normal();
+    let right = forward.cross(camera.up).normal();
+    let up = right.cross(forward);
+
+    let tan = (camera.fovy * 0.5).tan();
+    let aspect = width as f32 / height as f32;
+
+    for y in 0..height {
+        for x in 0..width {
+            if 1.0 >= depth.get([x, y].into()) {
+                continue;
+            }
+
+            let sx = ((x as f32 + 0.5) / width as f32 * 2.0 - 1.0) * tan * aspect;
+            let sy = ((y as f32 + 0.5) / height as f32 * 2.0 - 1.0) * tan;
+
+            image.set_color([x, y].into(), f(forward + right.scale(sx) + up.scale(sy)));
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnvironmentMapping {
+    Reflect,
+    /// Ratio of the indices of refraction, outside over inside.
+    Refract(f32),
+    /// Reflection and refraction blended by Schlick Fresnel.
+    Fresnel(f32),
+}
+
+/// Mirror or glass like surfaces looking up `cubemap`, `tint` multiplies the result.
+pub struct EnvironmentShader<'a> {
+    pub obj: &'a Obj,
+    pub uniforms: Uniforms,
+    pub cubemap: &'a CubeMap,
+    pub mapping: EnvironmentMapping,
+    pub tint: Vector3<f32>,
+    varying_position: [Vector3<f32>; 3],
+    varying_normal: [Vector3<f32>; 3],
+}
+
+impl<'a> EnvironmentShader<'a> {
+    pub fn new(obj: &'a Obj, uniforms: Uniforms, cubemap: &'a CubeMap, mapping: EnvironmentMapping) -> Self {
+        Self {
+            obj,
+            uniforms,
+            cubemap,
+            mapping,
+            tint: [1.0, 1.0, 1.0].into(),
+            varying_position: [0.0.into(); 3],
+            varying_normal: [0.0.into(); 3],
+        }
+    }
+}
+
+impl Shader for EnvironmentShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let position = transform_point(self.obj.vert(face, nthvert), &self.uniforms.model);
+
+        self.varying_position[nthvert] = position;
+        self.varying_normal[nthvert] = transform_normal(self.obj.normal(face, nthvert), &self.uniforms.normals);
+
+        embed(position, 1.0) * self.uniforms.view_projection
+    }
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor> {
+        let position = interpolate(&self.varying_position, fragment.bar);
+        let normal = interpolate(&self.varying_normal, fragment.bar).normal();
+
+        let i = (position - self.uniforms.eye).normal();
+        let lookup = |d: Vector3<f32>| Vector3::from(self.cubemap.sample(d));
+
+        let reflected = lookup(reflect(i, normal));
+
+        let color = match self.mapping {
+            EnvironmentMapping::Reflect => reflected,
+            EnvironmentMapping::Refract(eta) => refract(i, normal, eta).map_or(reflected, lookup),
+            EnvironmentMapping::Fresnel(eta) => {
+                let f0 = ((1.0 - eta) / (1.0 + eta)).powi(2);
+                let f = fresnel_schlick(-i.dot(normal), Vector3::from(f0)).x();
+
+                match refract(i, normal, eta) {
+                    Some(t) => reflected.scale(f) + lookup(t).scale(1.0 - f),
+                    None => reflected,
+                }
+            }
+        };
+
+        Some(to_color(color.hadamard(self.tint)))
+    }
+}
+
+fn sample_clamped<I: Image>(image: &I, uv: Vector2<f32>, wrap_x: bool) -> Vector4<f32> {
+    let width = image.get_width() as i32;
+    let height = image.get_height() as i32;
+
+    let x = uv.x() * width as f32 - 0.5;
+    let y = uv.y() * height as f32 - 0.5;
+
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+
+    let texel = |x: i32, y: i32| {
+        let x = if wrap_x { x.rem_euclid(width) } else { x.clamp(0, width - 1) };
+        to_float(image.get_color([x as u16, y.clamp(0, height - 1) as u16].into()))
+    };
+
+    let (x0, y0) = (x0 as i32, y0 as i32);
+
+    let bottom = texel(x0, y0).scale(1.0 - tx) + texel(x0 + 1, y0).scale(tx);
+    let top = texel(x0, y0 + 1).scale(1.0 - tx) + texel(x0 + 1, y0 + 1).scale(tx);
+
+    bottom.scale(1.0 - ty) + top.scale(ty)
+}
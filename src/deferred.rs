@@ -0,0 +1,268 @@
+//! <https://learnopengl.com/Advanced-Lighting/Deferred-Shading>
+//!
+
+use crate::gl::embed;
+use crate::gl::is_front_facing;
+use crate::gl::rasterize;
+use crate::gl::CullMode;
+use crate::gl::RenderState;
+use crate::img::sample;
+use crate::img::Buffer;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Hadamard;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::light::Light;
+use crate::obj::Obj;
+use crate::shader::interpolate;
+use crate::shader::phong;
+use crate::shader::to_color;
+use crate::shader::transform_normal;
+use crate::shader::transform_point;
+use crate::shader::Material;
+use crate::shader::Uniforms;
+use crate::tga::TGACoord;
+use crate::tga::TGAImage;
+
+/// Radiance below which point, spot and area lights are cut off when bounding them on screen.
+pub const LIGHT_CUTOFF: f32 = 1.0 / 256.0;
+
+/// Per pixel surface attributes, positions and normals are in world space and `material` holds the specular color and shininess.
+#[derive(Clone, Debug)]
+pub struct GBuffer {
+    pub depth: DepthBuffer,
+    pub position: Buffer<Vector3<f32>>,
+    pub normal: Buffer<Vector3<f32>>,
+    pub albedo: Buffer<Vector3<f32>>,
+    pub material: Buffer<Vector4<f32>>,
+}
+
+impl GBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            depth: DepthBuffer::new(width, height, f32::MAX),
+            position: Buffer::new(width, height, 0.0.into()),
+            normal: Buffer::new(width, height, 0.0.into()),
+            albedo: Buffer::new(width, height, 0.0.into()),
+            material: Buffer::new(width, height, 0.0.into()),
+        }
+    }
+
+    pub fn get_width(&self) -> u16 {
+        self.depth.get_width()
+    }
+
+    pub fn get_height(&self) -> u16 {
+        self.depth.get_height()
+    }
+
+    pub fn clear(&mut self) {
+        self.depth.fill(f32::MAX);
+        self.position.fill(0.0.into());
+        self.normal.fill(0.0.into());
+        self.albedo.fill(0.0.into());
+        self.material.fill(0.0.into());
+    }
+
+    /// Geometry pass, writes the closest surface of `obj` into every channel. `diffuse_map` modulates the albedo when present.
+    pub fn draw(&mut self, obj: &Obj, uniforms: &Uniforms, material: &Material, diffuse_map: Option<&TGAImage>, state: &RenderState) {
+        let width = self.get_width();
+        let height = self.get_height();
+
+        for face in 0..obj.fs.len() {
+            let positions = [0, 1, 2].map(|nthvert| transform_point(obj.vert(face, nthvert), &uniforms.model));
+            let normals = [0, 1, 2].map(|nthvert| transform_normal(obj.normal(face, nthvert), &uniforms.normals));
+            let uvs = [0, 1, 2].map(|nthvert| obj.uv(face, nthvert));
+
+            let clip = positions.map(|p| embed(p, 1.0) * uniforms.view_projection);
+
+            match state.cull {
+                CullMode::Back if !is_front_facing(&clip) => continue,
+                CullMode::Front if is_front_facing(&clip) => continue,
+                _ => {}
+            }
+
+            rasterize(&clip, width, height, |fragment| {
+                let coord = [fragment.x as u16, fragment.y as u16].into();
+
+                if !state.depth_test.test(fragment.depth, self.depth.get(coord)) {
+                    return;
+                }
+
+                if state.depth_write {
+                    self.depth.set(coord, fragment.depth);
+                }
+
+                let bar = fragment.bar;
+                let mut normal = interpolate(&normals, bar).normal();
+                if !fragment.front_facing {
+                    normal = normal.scale(-1.0);
+                }
+
+                let mut albedo = material.diffuse;
+                if let Some(diffuse_map) = diffuse_map {
+                    let uv = uvs[0].scale(bar.x()) + uvs[1].scale(bar.y()) + uvs[2].scale(bar.z());
+                    albedo = albedo.hadamard(Vector3::from(sample(diffuse_map, uv)));
+                }
+
+                self.position.set(coord, interpolate(&positions, bar));
+                self.normal.set(coord, normal);
+                self.albedo.set(coord, albedo);
+                self.material.set(coord, embed(material.specular, material.shininess));
+            });
+        }
+    }
+
+    /// Lighting pass, accumulates Blinn-Phong for every light of `uniforms` over its screen bounds plus `ambient` times albedo, and writes covered pixels to `image`.
+    pub fn shade<I: Image>(&self, image: &mut I, uniforms: &Uniforms, ambient: Vector3<f32>) {
+        let width = self.get_width().min(image.get_width());
+        let height = self.get_height().min(image.get_height());
+
+        let mut accumulation = Buffer::new(width, height, Vector3::from(0.0));
+
+        for y in 0..height {
+            for x in 0..width {
+                let coord = [x, y].into();
+                if self.covers(coord) {
+                    let occlusion = uniforms.occlusion.as_ref().map_or(1.0, |occlusion| occlusion.get(coord));
+                    accumulation.set(coord, ambient.hadamard(self.albedo.get(coord)).scale(occlusion));
+                }
+            }
+        }
+
+        for (i, light) in uniforms.lights.iter().enumerate() {
+            let (x0, y0, x1, y1) = light_bounds(light, uniforms, width, height);
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let coord = [x, y].into();
+                    if !self.covers(coord) {
+                        continue;
+                    }
+
+                    let p = self.position.get(coord);
+                    let n = self.normal.get(coord);
+                    let params = self.material.get(coord);
+
+                    let visibility = uniforms.visibility(i, p, n);
+
+                    if 0.0 >= visibility {
+                        continue;
+                    }
+
+                    let material = Material {
+                        ambient: 0.0.into(),
+                        diffuse: self.albedo.get(coord),
+                        specular: params.into(),
+                        shininess: params.w(),
+                        opacity: 1.0,
+                    };
+                    let v = (uniforms.eye - p).normal();
+
+                    let mut color = accumulation.get(coord);
+                    light.illuminate(p, |sample| color += sample.radiance.hadamard(phong(&material, n, sample.direction, v, true)).scale(visibility));
+                    accumulation.set(coord, color);
+                }
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                let coord = [x, y].into();
+                if self.covers(coord) {
+                    image.set_color(coord, to_color(uniforms.fog(accumulation.get(coord), self.position.get(coord))));
+                }
+            }
+        }
+    }
+
+    /// Depth stretched over the covered range, nearer is brighter.
+    pub fn depth_image(&self) -> TGAImage {
+        let covered = self.depth.as_slice().iter().filter(|&&d| 1.0 >= d);
+        let (near, far) = covered.fold((f32::MAX, f32::MIN), |(near, far), &d| (near.min(d), far.max(d)));
+        let range = (far - near).max(1e-6);
+
+        self.to_image(|gbuffer, coord| Vector3::from(1.0 - (gbuffer.depth.get(coord) - near) / range))
+    }
+
+    /// Positions mapped from `[-extent, extent]` to the full color range.
+    pub fn position_image(&self, extent: f32) -> TGAImage {
+        self.to_image(|gbuffer, coord| gbuffer.position.get(coord).scale(0.5 / extent) + Vector3::from(0.5))
+    }
+
+    pub fn normal_image(&self) -> TGAImage {
+        self.to_image(|gbuffer, coord| gbuffer.normal.get(coord).scale(0.5) + Vector3::from(0.5))
+    }
+
+    pub fn albedo_image(&self) -> TGAImage {
+        self.to_image(|gbuffer, coord| gbuffer.albedo.get(coord))
+    }
+
+    /// Specular color, shininess is not visualized.
+    pub fn material_image(&self) -> TGAImage {
+        self.to_image(|gbuffer, coord| gbuffer.material.get(coord).into())
+    }
+
+    fn covers(&self, coord: TGACoord) -> bool {
+        1.0 >= self.depth.get(coord)
+    }
+
+    fn to_image(&self, f: impl Fn(&Self, TGACoord) -> Vector3<f32>) -> TGAImage {
+        let mut image = TGAImage::new(self.get_width(), self.get_height());
+
+        for y in 0..self.get_height() {
+            for x in 0..self.get_width() {
+                let coord = [x, y].into();
+                if self.covers(coord) {
+                    image.set_color(coord, to_color(f(self, coord)));
+                }
+            }
+        }
+
+        image
+    }
+}
+
+/// Half open pixel rectangle `(x0, y0, x1, y1)` that `light` can reach, the whole screen for lights without falloff or when the bounds cross the eye plane.
+pub fn light_bounds(light: &Light, uniforms: &Uniforms, width: u16, height: u16) -> (u16, u16, u16, u16) {
+    let screen = (0, 0, width, height);
+
+    let position = match *light {
+        Light::Directional { .. } => return screen,
+        Light::Point { position, .. } | Light::Spot { position, .. } | Light::Area { position, .. } => position,
+    };
+
+    let Some(range) = light.range(LIGHT_CUTOFF) else {
+        return screen;
+    };
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+
+    for corner in 0..8 {
+        let offset: Vector3<f32> = [if 0 == corner & 1 { -range } else { range }, if 0 == corner & 2 { -range } else { range }, if 0 == corner & 4 { -range } else { range }].into();
+        let clip = embed(position + offset, 1.0) * uniforms.view_projection;
+
+        if 1e-6 >= clip.w() {
+            return screen;
+        }
+
+        let x = (clip.x() / clip.w() + 1.0) * 0.5 * width as f32;
+        let y = (clip.y() / clip.w() + 1.0) * 0.5 * height as f32;
+
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+
+    let clamp = |v: f32, size: u16| v.clamp(0.0, size as f32) as u16;
+
+    (clamp(min_x.floor(), width), clamp(min_y.floor(), height), clamp(max_x.ceil(), width), clamp(max_y.ceil(), height))
+}
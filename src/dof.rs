@@ -0,0 +1,116 @@
+//! <https://en.wikipedia.org/wiki/Circle_of_confusion>
+//! <https://developer.nvidia.com/gpugems/gpugems/part-iv-image-processing/chapter-23-depth-field-survey-techniques>
+//!
+
+use std::f32::consts::PI;
+
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::post::sample_pixel;
+use crate::post::Pass;
+use crate::shader::to_rgba;
+use crate::shader::Camera;
+
+/// Thin lens depth of field gathered over a golden angle disk, `aperture` is the lens diameter and `focus_distance` the sharp distance, both in world units.
+#[derive(Clone, Copy, Debug)]
+pub struct DepthOfField<'a> {
+    pub depth: &'a DepthBuffer,
+    pub near: f32,
+    pub far: f32,
+    pub fovy: f32,
+    pub aperture: f32,
+    pub focus_distance: f32,
+    /// Largest circle of confusion radius in pixels.
+    pub max_radius: f32,
+    pub samples: u32,
+}
+
+impl<'a> DepthOfField<'a> {
+    /// `depth` must come from a perspective render with `camera`.
+    pub fn new(depth: &'a DepthBuffer, camera: &Camera, aperture: f32, focus_distance: f32) -> Self {
+        Self {
+            depth,
+            near: camera.near,
+            far: camera.far,
+            fovy: camera.fovy,
+            aperture,
+            focus_distance,
+            max_radius: 12.0,
+            samples: 48,
+        }
+    }
+
+    /// Eye space distance of a depth buffer value, background pixels are at infinity.
+    pub fn linear_depth(&self, depth: f32) -> f32 {
+        if 1.0 < depth {
+            return f32::INFINITY;
+        }
+
+        let ndc = depth * 2.0 - 1.0;
+
+        if self.far.is_infinite() {
+            2.0 * self.near / (1.0 - ndc).max(1e-7)
+        } else {
+            2.0 * self.near * self.far / (self.far + self.near - ndc * (self.far - self.near))
+        }
+    }
+
+    /// Circle of confusion radius in pixels at eye distance `z` for an image `height` pixels tall.
+    pub fn coc(&self, z: f32, height: u16) -> f32 {
+        let focal = height as f32 * 0.5 / (self.fovy * 0.5).tan();
+        let diameter = if z.is_infinite() { self.aperture * focal / self.focus_distance } else { self.aperture * focal * (z - self.focus_distance).abs() / (z * self.focus_distance) };
+
+        (diameter * 0.5).min(self.max_radius)
+    }
+}
+
+impl Pass for DepthOfField<'_> {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let width = source.get_width().min(self.depth.get_width());
+        let height = source.get_height().min(self.depth.get_height());
+
+        let coc = |x: i32, y: i32| {
+            let coord = [x.clamp(0, width as i32 - 1) as u16, y.clamp(0, height as i32 - 1) as u16].into();
+            let z = self.linear_depth(self.depth.get(coord));
+            (z, self.coc(z, height))
+        };
+
+        let golden = PI * (3.0 - 5.0_f32.sqrt());
+
+        for y in 0..height {
+            for x in 0..width {
+                let (z, radius) = coc(x as i32, y as i32);
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                if 0.5 > radius {
+                    target.set_color([x, y].into(), source.get_color([x, y].into()));
+                    continue;
+                }
+
+                let mut sum = Vector4::from(0.0);
+                let mut weight = 0.0;
+
+                let samples = self.samples.max(1);
+                for i in 0..samples {
+                    let r = radius * ((i as f32 + 0.5) / samples as f32).sqrt();
+                    let theta = i as f32 * golden;
+                    let (sx, sy) = (px + r * theta.cos(), py + r * theta.sin());
+
+                    let (sz, sample_radius) = coc(sx.floor() as i32, sy.floor() as i32);
+
+                    // sharper surfaces behind the pixel must not bleed into its blur
+                    let w = if sz < z { 1.0 } else { (sample_radius - r + 1.0).clamp(0.0, 1.0) };
+
+                    sum += sample_pixel(source, sx, sy).scale(w);
+                    weight += w;
+                }
+
+                let color = if 0.0 < weight { sum.scale(1.0 / weight) } else { sample_pixel(source, px, py) };
+                target.set_color([x, y].into(), to_rgba(Vector3::from(color), color[0][3]));
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! <https://registry.khronos.org/OpenGL-Refpages/gl2.1/xhtml/glFog.xml>
+//! <https://iquilezles.org/articles/fog/>
+//!
+
+use crate::img::to_float;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Identity;
+use crate::la::Inverse;
+use crate::la::Magnitude;
+use crate::la::Matrix;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::post::Pass;
+use crate::shader::to_rgba;
+use crate::shader::Camera;
+
+/// Distance falloff, see `glFog`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogMode {
+    Linear { start: f32, end: f32 },
+    Exponential(f32),
+    ExponentialSquared(f32),
+}
+
+/// Density `density * exp(-falloff * (y - base))` integrated along the view ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightFog {
+    pub density: f32,
+    pub falloff: f32,
+    pub base: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub mode: Option<FogMode>,
+    pub height: Option<HeightFog>,
+    pub color: Vector3<f32>,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            mode: Some(FogMode::Exponential(0.1)),
+            height: None,
+            color: [0.7, 0.75, 0.8].into(),
+        }
+    }
+}
+
+impl Fog {
+    /// Fraction of the surface color reaching `eye` from `p`, 1 is no fog.
+    pub fn visibility(&self, eye: Vector3<f32>, p: Vector3<f32>) -> f32 {
+        let distance = (p - eye).magnitude();
+
+        let mut f = match self.mode {
+            // an empty range is a hard step at `start`
+            Some(FogMode::Linear { start, end }) if start >= end => {
+                if start > distance {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Some(FogMode::Linear { start, end }) => ((end - distance) / (end - start)).clamp(0.0, 1.0),
+            Some(FogMode::Exponential(density)) => (-density * distance).exp(),
+            Some(FogMode::ExponentialSquared(density)) => (-(density * distance).powi(2)).exp(),
+            None => 1.0,
+        };
+
+        if let Some(height) = self.height {
+            let dy = height.falloff * (p.y() - eye.y());
+            let integral = if 1e-4 < dy.abs() { (1.0 - (-dy).exp()) / dy } else { 1.0 - dy * 0.5 };
+            f *= (-height.density * (-height.falloff * (eye.y() - height.base)).exp() * integral * distance).exp();
+        }
+
+        f
+    }
+
+    pub fn apply(&self, color: Vector3<f32>, eye: Vector3<f32>, p: Vector3<f32>) -> Vector3<f32> {
+        let f = self.visibility(eye, p);
+
+        self.color + (color - self.color).scale(f)
+    }
+}
+
+/// Fog reconstructed from the depth buffer of a render with the same camera, uncovered pixels are left as they are.
+#[derive(Clone, Copy, Debug)]
+pub struct FogPass<'a> {
+    pub fog: Fog,
+    pub depth: &'a DepthBuffer,
+    pub eye: Vector3<f32>,
+    pub inverse_view_projection: Matrix<f32, 4, 4>,
+}
+
+impl<'a> FogPass<'a> {
+    pub fn new(fog: Fog, depth: &'a DepthBuffer, camera: &Camera, aspect: f32) -> Self {
+        Self {
+            fog,
+            depth,
+            eye: camera.eye,
+            inverse_view_projection: (camera.view() * camera.projection(aspect)).inverse().unwrap_or(Matrix::identity()),
+        }
+    }
+}
+
+impl Pass for FogPass<'_> {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let width = source.get_width();
+        let height = source.get_height();
+
+        for y in 0..height {
+            for x in 0..width {
+                let coord = [x, y].into();
+                let color = source.get_color(coord);
+
+                let inside = x < self.depth.get_width() && y < self.depth.get_height();
+                let depth = if inside { self.depth.get(coord) } else { f32::MAX };
+                if 1.0 < depth {
+                    target.set_color(coord, color);
+                    continue;
+                }
+
+                let ndc: Vector4<f32> = [(x as f32 + 0.5) / width as f32 * 2.0 - 1.0, (y as f32 + 0.5) / height as f32 * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0].into();
+                let world = ndc * self.inverse_view_projection;
+                let p: Vector3<f32> = [world.x() / world.w(), world.y() / world.w(), world.z() / world.w()].into();
+
+                let c = to_float(color);
+                target.set_color(coord, to_rgba(self.fog.apply(Vector3::from(c), self.eye, p), c[0][3]));
+            }
+        }
+    }
+}
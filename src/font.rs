@@ -0,0 +1,329 @@
+//! <https://en.wikipedia.org/wiki/Glyph_Bitmap_Distribution_Format>
+//!
+//!
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::Path;
+
+use crate::img::Image;
+use crate::la::Vector2;
+use crate::la::X;
+use crate::la::Y;
+use crate::tga::TGAColor;
+
+#[rustfmt::skip]
+const FONT8X8_ASCII: [[u8; 8]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x18, 0x3C, 0x3C, 0x18, 0x18, 0x00, 0x18, 0x00], // '!'
+    [0x36, 0x36, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x36, 0x36, 0x7F, 0x36, 0x7F, 0x36, 0x36, 0x00], // '#'
+    [0x0C, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x0C, 0x00], // '$'
+    [0x00, 0x63, 0x33, 0x18, 0x0C, 0x66, 0x63, 0x00], // '%'
+    [0x1C, 0x36, 0x1C, 0x6E, 0x3B, 0x33, 0x6E, 0x00], // '&'
+    [0x06, 0x06, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00], // '\''
+    [0x18, 0x0C, 0x06, 0x06, 0x06, 0x0C, 0x18, 0x00], // '('
+    [0x06, 0x0C, 0x18, 0x18, 0x18, 0x0C, 0x06, 0x00], // ')'
+    [0x00, 0x66, 0x3C, 0xFF, 0x3C, 0x66, 0x00, 0x00], // '*'
+    [0x00, 0x0C, 0x0C, 0x3F, 0x0C, 0x0C, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ','
+    [0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C, 0x00], // '.'
+    [0x60, 0x30, 0x18, 0x0C, 0x06, 0x03, 0x01, 0x00], // '/'
+    [0x3E, 0x63, 0x73, 0x7B, 0x6F, 0x67, 0x3E, 0x00], // '0'
+    [0x0C, 0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x3F, 0x00], // '1'
+    [0x1E, 0x33, 0x30, 0x1C, 0x06, 0x33, 0x3F, 0x00], // '2'
+    [0x1E, 0x33, 0x30, 0x1C, 0x30, 0x33, 0x1E, 0x00], // '3'
+    [0x38, 0x3C, 0x36, 0x33, 0x7F, 0x30, 0x78, 0x00], // '4'
+    [0x3F, 0x03, 0x1F, 0x30, 0x30, 0x33, 0x1E, 0x00], // '5'
+    [0x1C, 0x06, 0x03, 0x1F, 0x33, 0x33, 0x1E, 0x00], // '6'
+    [0x3F, 0x33, 0x30, 0x18, 0x0C, 0x0C, 0x0C, 0x00], // '7'
+    [0x1E, 0x33, 0x33, 0x1E, 0x33, 0x33, 0x1E, 0x00], // '8'
+    [0x1E, 0x33, 0x33, 0x3E, 0x30, 0x18, 0x0E, 0x00], // '9'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x00], // ':'
+    [0x00, 0x0C, 0x0C, 0x00, 0x00, 0x0C, 0x0C, 0x06], // ';'
+    [0x18, 0x0C, 0x06, 0x03, 0x06, 0x0C, 0x18, 0x00], // '<'
+    [0x00, 0x00, 0x3F, 0x00, 0x00, 0x3F, 0x00, 0x00], // '='
+    [0x06, 0x0C, 0x18, 0x30, 0x18, 0x0C, 0x06, 0x00], // '>'
+    [0x1E, 0x33, 0x30, 0x18, 0x0C, 0x00, 0x0C, 0x00], // '?'
+    [0x3E, 0x63, 0x7B, 0x7B, 0x7B, 0x03, 0x1E, 0x00], // '@'
+    [0x0C, 0x1E, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x00], // 'A'
+    [0x3F, 0x66, 0x66, 0x3E, 0x66, 0x66, 0x3F, 0x00], // 'B'
+    [0x3C, 0x66, 0x03, 0x03, 0x03, 0x66, 0x3C, 0x00], // 'C'
+    [0x1F, 0x36, 0x66, 0x66, 0x66, 0x36, 0x1F, 0x00], // 'D'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x46, 0x7F, 0x00], // 'E'
+    [0x7F, 0x46, 0x16, 0x1E, 0x16, 0x06, 0x0F, 0x00], // 'F'
+    [0x3C, 0x66, 0x03, 0x03, 0x73, 0x66, 0x7C, 0x00], // 'G'
+    [0x33, 0x33, 0x33, 0x3F, 0x33, 0x33, 0x33, 0x00], // 'H'
+    [0x1E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'I'
+    [0x78, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E, 0x00], // 'J'
+    [0x67, 0x66, 0x36, 0x1E, 0x36, 0x66, 0x67, 0x00], // 'K'
+    [0x0F, 0x06, 0x06, 0x06, 0x46, 0x66, 0x7F, 0x00], // 'L'
+    [0x63, 0x77, 0x7F, 0x7F, 0x6B, 0x63, 0x63, 0x00], // 'M'
+    [0x63, 0x67, 0x6F, 0x7B, 0x73, 0x63, 0x63, 0x00], // 'N'
+    [0x1C, 0x36, 0x63, 0x63, 0x63, 0x36, 0x1C, 0x00], // 'O'
+    [0x3F, 0x66, 0x66, 0x3E, 0x06, 0x06, 0x0F, 0x00], // 'P'
+    [0x1E, 0x33, 0x33, 0x33, 0x3B, 0x1E, 0x38, 0x00], // 'Q'
+    [0x3F, 0x66, 0x66, 0x3E, 0x36, 0x66, 0x67, 0x00], // 'R'
+    [0x1E, 0x33, 0x07, 0x0E, 0x38, 0x33, 0x1E, 0x00], // 'S'
+    [0x3F, 0x2D, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'T'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x3F, 0x00], // 'U'
+    [0x33, 0x33, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'V'
+    [0x63, 0x63, 0x63, 0x6B, 0x7F, 0x77, 0x63, 0x00], // 'W'
+    [0x63, 0x63, 0x36, 0x1C, 0x1C, 0x36, 0x63, 0x00], // 'X'
+    [0x33, 0x33, 0x33, 0x1E, 0x0C, 0x0C, 0x1E, 0x00], // 'Y'
+    [0x7F, 0x63, 0x31, 0x18, 0x4C, 0x66, 0x7F, 0x00], // 'Z'
+    [0x1E, 0x06, 0x06, 0x06, 0x06, 0x06, 0x1E, 0x00], // '['
+    [0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x40, 0x00], // '\\'
+    [0x1E, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1E, 0x00], // ']'
+    [0x08, 0x1C, 0x36, 0x63, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF], // '_'
+    [0x0C, 0x0C, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x1E, 0x30, 0x3E, 0x33, 0x6E, 0x00], // 'a'
+    [0x07, 0x06, 0x06, 0x3E, 0x66, 0x66, 0x3B, 0x00], // 'b'
+    [0x00, 0x00, 0x1E, 0x33, 0x03, 0x33, 0x1E, 0x00], // 'c'
+    [0x38, 0x30, 0x30, 0x3E, 0x33, 0x33, 0x6E, 0x00], // 'd'
+    [0x00, 0x00, 0x1E, 0x33, 0x3F, 0x03, 0x1E, 0x00], // 'e'
+    [0x1C, 0x36, 0x06, 0x0F, 0x06, 0x06, 0x0F, 0x00], // 'f'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'g'
+    [0x07, 0x06, 0x36, 0x6E, 0x66, 0x66, 0x67, 0x00], // 'h'
+    [0x0C, 0x00, 0x0E, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'i'
+    [0x30, 0x00, 0x30, 0x30, 0x30, 0x33, 0x33, 0x1E], // 'j'
+    [0x07, 0x06, 0x66, 0x36, 0x1E, 0x36, 0x67, 0x00], // 'k'
+    [0x0E, 0x0C, 0x0C, 0x0C, 0x0C, 0x0C, 0x1E, 0x00], // 'l'
+    [0x00, 0x00, 0x33, 0x7F, 0x7F, 0x6B, 0x63, 0x00], // 'm'
+    [0x00, 0x00, 0x1F, 0x33, 0x33, 0x33, 0x33, 0x00], // 'n'
+    [0x00, 0x00, 0x1E, 0x33, 0x33, 0x33, 0x1E, 0x00], // 'o'
+    [0x00, 0x00, 0x3B, 0x66, 0x66, 0x3E, 0x06, 0x0F], // 'p'
+    [0x00, 0x00, 0x6E, 0x33, 0x33, 0x3E, 0x30, 0x78], // 'q'
+    [0x00, 0x00, 0x3B, 0x6E, 0x66, 0x06, 0x0F, 0x00], // 'r'
+    [0x00, 0x00, 0x3E, 0x03, 0x1E, 0x30, 0x1F, 0x00], // 's'
+    [0x08, 0x0C, 0x3E, 0x0C, 0x0C, 0x2C, 0x18, 0x00], // 't'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x33, 0x6E, 0x00], // 'u'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x1E, 0x0C, 0x00], // 'v'
+    [0x00, 0x00, 0x63, 0x6B, 0x7F, 0x7F, 0x36, 0x00], // 'w'
+    [0x00, 0x00, 0x63, 0x36, 0x1C, 0x36, 0x63, 0x00], // 'x'
+    [0x00, 0x00, 0x33, 0x33, 0x33, 0x3E, 0x30, 0x1F], // 'y'
+    [0x00, 0x00, 0x3F, 0x19, 0x0C, 0x26, 0x3F, 0x00], // 'z'
+    [0x38, 0x0C, 0x0C, 0x07, 0x0C, 0x0C, 0x38, 0x00], // '{'
+    [0x18, 0x18, 0x18, 0x00, 0x18, 0x18, 0x18, 0x00], // '|'
+    [0x07, 0x0C, 0x0C, 0x38, 0x0C, 0x0C, 0x07, 0x00], // '}'
+    [0x6E, 0x3B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
+
+/// Bitmap rows run from top to bottom, `(x_offset, y_offset)` places the bottom left corner relative to the pen on the baseline.
+#[derive(Clone, Debug, Default)]
+pub struct Glyph {
+    pub width: i32,
+    pub height: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub advance: i32,
+    pub bitmap: Vec<bool>,
+}
+
+impl Glyph {
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        self.bitmap[(x + y * self.width) as usize]
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BitmapFont {
+    pub ascent: i32,
+    pub descent: i32,
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl BitmapFont {
+    /// The embedded 8x8 monospaced font covering printable ASCII.
+    pub fn ascii() -> Self {
+        let mut glyphs = HashMap::new();
+
+        for (i, rows) in FONT8X8_ASCII.iter().enumerate() {
+            let mut bitmap = Vec::with_capacity(64);
+            for row in rows {
+                for x in 0..8 {
+                    bitmap.push(0 != row & (1 << x));
+                }
+            }
+
+            glyphs.insert(
+                (0x20 + i as u8) as char,
+                Glyph {
+                    width: 8,
+                    height: 8,
+                    x_offset: 0,
+                    y_offset: -1,
+                    advance: 8,
+                    bitmap,
+                },
+            );
+        }
+
+        Self { ascent: 7, descent: 1, glyphs }
+    }
+
+    pub fn load_bdf<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut file = OpenOptions::new().read(true).open(path)?;
+
+        let mut content = String::new();
+
+        file.read_to_string(&mut content)?;
+
+        Self::from_bdf(&content)
+    }
+
+    pub fn from_bdf(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut font = Self::default();
+
+        let mut bbx = [0i32; 4];
+        let mut default_advance = 0;
+
+        let mut lines = content.lines();
+
+        while let Some(line) = lines.next() {
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("FONTBOUNDINGBOX") => {
+                    let ds: Vec<i32> = words.map(|w| w.parse()).collect::<Result<_, _>>()?;
+                    if 4 > ds.len() {
+                        return Err("FONTBOUNDINGBOX 解释失败".into());
+                    }
+                    glyph_size(ds[0], ds[1]).ok_or("FONTBOUNDINGBOX 解释失败")?;
+                    bbx = [ds[0], ds[1], ds[2], ds[3]];
+                    default_advance = ds[0];
+                }
+                Some("FONT_ASCENT") => font.ascent = words.next().ok_or("FONT_ASCENT 解释失败")?.parse()?,
+                Some("FONT_DESCENT") => font.descent = words.next().ok_or("FONT_DESCENT 解释失败")?.parse()?,
+                Some("STARTCHAR") => {
+                    let mut encoding = -1;
+                    let mut in_bitmap = false;
+                    let mut glyph = Glyph {
+                        width: bbx[0],
+                        height: bbx[1],
+                        x_offset: bbx[2],
+                        y_offset: bbx[3],
+                        advance: default_advance,
+                        bitmap: Vec::new(),
+                    };
+
+                    for line in lines.by_ref() {
+                        let mut words = line.split_whitespace();
+
+                        match words.next() {
+                            Some("ENCODING") => encoding = words.next().ok_or("ENCODING 解释失败")?.parse()?,
+                            Some("DWIDTH") => glyph.advance = words.next().ok_or("DWIDTH 解释失败")?.parse()?,
+                            Some("BBX") => {
+                                let ds: Vec<i32> = words.map(|w| w.parse()).collect::<Result<_, _>>()?;
+                                if 4 > ds.len() || glyph_size(ds[0], ds[1]).is_none() {
+                                    return Err("BBX 解释失败".into());
+                                }
+                                (glyph.width, glyph.height, glyph.x_offset, glyph.y_offset) = (ds[0], ds[1], ds[2], ds[3]);
+                            }
+                            Some("BITMAP") => in_bitmap = true,
+                            Some("ENDCHAR") => break,
+                            Some(word) if in_bitmap => {
+                                let bits = u64::from_str_radix(word, 16)?;
+                                let count = word.len() as i32 * 4;
+                                for x in 0..glyph.width {
+                                    glyph.bitmap.push(x < count && 0 != (bits >> (count - 1 - x)) & 1);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    glyph.bitmap.resize(glyph_size(glyph.width, glyph.height).ok_or("BBX 解释失败")?, false);
+
+                    if let Some(c) = u32::try_from(encoding).ok().and_then(char::from_u32) {
+                        font.glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if 0 == font.ascent && 0 == font.descent {
+            font.ascent = bbx[1] + bbx[3];
+            font.descent = -bbx[3];
+        }
+
+        Ok(font)
+    }
+
+    pub fn get_glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    pub fn line_height(&self) -> i32 {
+        self.ascent + self.descent
+    }
+
+    /// Width of the widest line and height of all lines, in unscaled pixels.
+    pub fn measure(&self, text: &str) -> Vector2<i32> {
+        let mut width = 0;
+        let mut lines = 0;
+
+        for line in text.split('\n') {
+            width = width.max(line.chars().filter_map(|c| self.get_glyph(c)).map(|g| g.advance).sum());
+            lines += 1;
+        }
+
+        [width, lines * self.line_height()].into()
+    }
+}
+
+/// Draws `text` with its first baseline starting at `pos`, every font pixel becomes a `scale` x `scale` block blended with the alpha of `color`.
+pub fn draw_text<I: Image>(image: &mut I, font: &BitmapFont, pos: Vector2<i32>, text: &str, color: TGAColor, scale: u32) {
+    let scale = scale.max(1) as i32;
+
+    let mut pen_x = pos.x();
+    let mut baseline = pos.y();
+
+    for c in text.chars() {
+        if '\n' == c {
+            pen_x = pos.x();
+            baseline -= font.line_height() * scale;
+            continue;
+        }
+
+        let Some(glyph) = font.get_glyph(c) else {
+            continue;
+        };
+
+        for gy in 0..glyph.height {
+            for gx in 0..glyph.width {
+                if !glyph.get(gx, gy) {
+                    continue;
+                }
+
+                let x = pen_x + (glyph.x_offset + gx) * scale;
+                let y = baseline + (glyph.y_offset + glyph.height - 1 - gy) * scale;
+
+                for sy in 0..scale {
+                    for sx in 0..scale {
+                        image.blend_pixel(x + sx, y + sy, color, 1.0);
+                    }
+                }
+            }
+        }
+
+        pen_x += glyph.advance * scale;
+    }
+}
+
+/// Pixel count of a `width` x `height` glyph box, `None` for negative or oversized boxes. Empty boxes are allowed, BDF
+/// fonts commonly give the space glyph `BBX 0 0 0 0`.
+fn glyph_size(width: i32, height: i32) -> Option<usize> {
+    const MAX_GLYPH_PIXELS: usize = 1 << 20;
+
+    if 0 > width || 0 > height {
+        return None;
+    }
+
+    (width as usize).checked_mul(height as usize).filter(|&size| MAX_GLYPH_PIXELS >= size)
+}
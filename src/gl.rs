@@ -0,0 +1,681 @@
+//! <https://github.com/ssloy/tinyrenderer/wiki>
+//!
+//!
+
+use crate::cg::bresenham;
+use crate::cg::stroke_path;
+use crate::cg::LineCap;
+use crate::cg::Path;
+use crate::cg::Stroke;
+use crate::img::to_float;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::img::StencilBuffer;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Matrix;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::tga::TGAColor;
+use crate::tga::TGACoord;
+
+pub fn embed(v: Vector3<f32>, w: f32) -> Vector4<f32> {
+    [v.x(), v.y(), v.z(), w].into()
+}
+
+pub fn project(v: Vector4<f32>) -> Vector3<f32> {
+    [v.x() / v.w(), v.y() / v.w(), v.z() / v.w()].into()
+}
+
+/// Matrices in this module act on row vectors, `p * model * view * projection` yields clip coordinates.
+pub fn lookat(eye: Vector3<f32>, center: Vector3<f32>, up: Vector3<f32>) -> Matrix<f32, 4, 4> {
+    let z = (eye - center).normal();
+    let x = up.cross(z).normal();
+    let y = z.cross(x);
+
+    [
+        [x.x(), y.x(), z.x(), 0.0],
+        [x.y(), y.y(), z.y(), 0.0],
+        [x.z(), y.z(), z.z(), 0.0],
+        [-x.dot(eye), -y.dot(eye), -z.dot(eye), 1.0],
+    ]
+    .into()
+}
+
+pub fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> Matrix<f32, 4, 4> {
+    let f = 1.0 / (fovy * 0.5).tan();
+
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (far + near) / (near - far), -1.0],
+        [0.0, 0.0, 2.0 * far * near / (near - far), 0.0],
+    ]
+    .into()
+}
+
+/// Perspective with the far plane at infinity, nudged by a small epsilon so that points at infinity (`w = 0`) stay inside the clip volume.
+pub fn infinite_perspective(fovy: f32, aspect: f32, near: f32) -> Matrix<f32, 4, 4> {
+    let f = 1.0 / (fovy * 0.5).tan();
+    let epsilon = 1e-6;
+
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, epsilon - 1.0, -1.0],
+        [0.0, 0.0, (epsilon - 2.0) * near, 0.0],
+    ]
+    .into()
+}
+
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Matrix<f32, 4, 4> {
+    [
+        [2.0 / (right - left), 0.0, 0.0, 0.0],
+        [0.0, 2.0 / (top - bottom), 0.0, 0.0],
+        [0.0, 0.0, -2.0 / (far - near), 0.0],
+        [-(right + left) / (right - left), -(top + bottom) / (top - bottom), -(far + near) / (far - near), 1.0],
+    ]
+    .into()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compare {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl Compare {
+    pub fn test<T: PartialOrd>(self, value: T, reference: T) -> bool {
+        match self {
+            Compare::Never => false,
+            Compare::Less => value < reference,
+            Compare::Equal => value == reference,
+            Compare::LessEqual => value <= reference,
+            Compare::Greater => value > reference,
+            Compare::NotEqual => value != reference,
+            Compare::GreaterEqual => value >= reference,
+            Compare::Always => true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+}
+
+impl BlendFactor {
+    fn weight(self, channel: usize, src: &Vector4<f32>, dst: &Vector4<f32>) -> f32 {
+        match self {
+            BlendFactor::Zero => 0.0,
+            BlendFactor::One => 1.0,
+            BlendFactor::SrcColor => src[0][channel],
+            BlendFactor::OneMinusSrcColor => 1.0 - src[0][channel],
+            BlendFactor::DstColor => dst[0][channel],
+            BlendFactor::OneMinusDstColor => 1.0 - dst[0][channel],
+            BlendFactor::SrcAlpha => *src.w(),
+            BlendFactor::OneMinusSrcAlpha => 1.0 - src.w(),
+            BlendFactor::DstAlpha => *dst.w(),
+            BlendFactor::OneMinusDstAlpha => 1.0 - dst.w(),
+        }
+    }
+}
+
+/// `Min` and `Max` ignore the factors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendEquation {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    fn combine(self, src: f32, src_weight: f32, dst: f32, dst_weight: f32) -> f32 {
+        match self {
+            BlendEquation::Add => src * src_weight + dst * dst_weight,
+            BlendEquation::Subtract => src * src_weight - dst * dst_weight,
+            BlendEquation::ReverseSubtract => dst * dst_weight - src * src_weight,
+            BlendEquation::Min => src.min(dst),
+            BlendEquation::Max => src.max(dst),
+        }
+    }
+}
+
+/// Separate color and alpha blending of the fragment color (src) with the pixel already in the image (dst).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Blend {
+    pub src_color: BlendFactor,
+    pub dst_color: BlendFactor,
+    pub color_equation: BlendEquation,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+    pub alpha_equation: BlendEquation,
+}
+
+impl Blend {
+    /// Straight alpha source-over.
+    pub fn alpha() -> Self {
+        Self {
+            src_color: BlendFactor::SrcAlpha,
+            dst_color: BlendFactor::OneMinusSrcAlpha,
+            color_equation: BlendEquation::Add,
+            src_alpha: BlendFactor::One,
+            dst_alpha: BlendFactor::OneMinusSrcAlpha,
+            alpha_equation: BlendEquation::Add,
+        }
+    }
+
+    /// Source-over for colors already multiplied by their alpha.
+    pub fn premultiplied() -> Self {
+        Self {
+            src_color: BlendFactor::One,
+            ..Self::alpha()
+        }
+    }
+
+    pub fn additive() -> Self {
+        Self {
+            src_color: BlendFactor::One,
+            dst_color: BlendFactor::One,
+            color_equation: BlendEquation::Add,
+            src_alpha: BlendFactor::One,
+            dst_alpha: BlendFactor::One,
+            alpha_equation: BlendEquation::Add,
+        }
+    }
+
+    pub fn apply(&self, src: TGAColor, dst: TGAColor) -> TGAColor {
+        let (s, d) = (to_float(src), to_float(dst));
+
+        let channel = |i: usize| {
+            let (src_factor, dst_factor, equation) = if 3 == i { (self.src_alpha, self.dst_alpha, self.alpha_equation) } else { (self.src_color, self.dst_color, self.color_equation) };
+            let value = equation.combine(s[0][i], src_factor.weight(i, &s, &d), d[0][i], dst_factor.weight(i, &s, &d));
+            (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+        };
+
+        [channel(0), channel(1), channel(2), channel(3)].into()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    IncrementClamp,
+    DecrementClamp,
+    Invert,
+    IncrementWrap,
+    DecrementWrap,
+}
+
+impl StencilOp {
+    pub fn apply(self, value: u8, reference: u8) -> u8 {
+        match self {
+            StencilOp::Keep => value,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => reference,
+            StencilOp::IncrementClamp => value.saturating_add(1),
+            StencilOp::DecrementClamp => value.saturating_sub(1),
+            StencilOp::Invert => !value,
+            StencilOp::IncrementWrap => value.wrapping_add(1),
+            StencilOp::DecrementWrap => value.wrapping_sub(1),
+        }
+    }
+}
+
+/// Test and operations for one facing, `compare` is evaluated as `reference compare stored` after masking both sides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StencilFace {
+    pub compare: Compare,
+    pub fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl Default for StencilFace {
+    fn default() -> Self {
+        Self {
+            compare: Compare::Always,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StencilState {
+    pub front: StencilFace,
+    pub back: StencilFace,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            front: StencilFace::default(),
+            back: StencilFace::default(),
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+        }
+    }
+}
+
+impl StencilState {
+    /// Same test and operations for both facings.
+    pub fn new(face: StencilFace, reference: u8) -> Self {
+        Self {
+            front: face,
+            back: face,
+            reference,
+            ..Self::default()
+        }
+    }
+
+    pub fn test(&self, face: &StencilFace, stored: u8) -> bool {
+        face.compare.test(self.reference & self.read_mask, stored & self.read_mask)
+    }
+
+    /// Applies `op` to the stored value, only the bits of `write_mask` change.
+    pub fn update(&self, stencil: &mut StencilBuffer, coord: TGACoord, op: StencilOp) {
+        let stored = stencil.get(coord);
+        let value = op.apply(stored, self.reference);
+
+        stencil.set(coord, (stored & !self.write_mask) | (value & self.write_mask));
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderState {
+    pub cull: CullMode,
+    pub depth_test: Compare,
+    pub depth_write: bool,
+    pub color_write: bool,
+    /// Fragments overwrite the image when `None`.
+    pub blend: Option<Blend>,
+    /// Only used by the stencil aware draws.
+    pub stencil: Option<StencilState>,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self {
+            cull: CullMode::Back,
+            depth_test: Compare::Less,
+            depth_write: true,
+            color_write: true,
+            blend: None,
+            stencil: None,
+        }
+    }
+}
+
+impl RenderState {
+    /// Alpha blended without depth writes, for transparent geometry drawn after the opaque pass.
+    pub fn transparent() -> Self {
+        Self {
+            cull: CullMode::None,
+            depth_write: false,
+            blend: Some(Blend::alpha()),
+            ..Self::default()
+        }
+    }
+}
+
+pub const MAX_SAMPLES: usize = 32;
+
+/// `bar` holds perspective correct barycentric coordinates relative to the three vertices handed to the rasterizer.
+#[derive(Clone, Copy, Debug)]
+pub struct Fragment {
+    pub x: i32,
+    pub y: i32,
+    pub depth: f32,
+    pub bar: Vector3<f32>,
+    pub front_facing: bool,
+    /// Bit `i` is set when sample `i` is covered, always `1` for single sampled rasterization.
+    pub coverage: u32,
+}
+
+pub trait Shader {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32>;
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor>;
+}
+
+/// Counter-clockwise triangles on screen face the viewer.
+pub fn is_front_facing(clip: &[Vector4<f32>; 3]) -> bool {
+    let a = Vector3::from([clip[0].x(), clip[0].y(), clip[0].w()]);
+    let b = Vector3::from([clip[1].x(), clip[1].y(), clip[1].w()]);
+    let c = Vector3::from([clip[2].x(), clip[2].y(), clip[2].w()]);
+
+    0.0 < a.dot(b.cross(c))
+}
+
+/// Clips the triangle against the near, far and `w = 0` planes and visits every covered pixel center of a `width` x `height` target.
+pub fn rasterize<F: FnMut(&Fragment)>(clip: &[Vector4<f32>; 3], width: u16, height: u16, mut f: F) {
+    rasterize_samples(clip, width, height, &[[0.5, 0.5].into()], |fragment, _| f(fragment));
+}
+
+/// Same as `rasterize` with coverage tested at each of `samples` (offsets inside the pixel, at most `MAX_SAMPLES`), `f` also gets the depth at every sample and is called once per pixel with attributes taken at the pixel center.
+pub fn rasterize_samples<F: FnMut(&Fragment, &[f32])>(clip: &[Vector4<f32>; 3], width: u16, height: u16, samples: &[Vector2<f32>], mut f: F) {
+    let front_facing = is_front_facing(clip);
+    let samples = &samples[..samples.len().min(MAX_SAMPLES)];
+
+    let mut polygon: Vec<(Vector4<f32>, Vector3<f32>)> = vec![(clip[0], [1.0, 0.0, 0.0].into()), (clip[1], [0.0, 1.0, 0.0].into()), (clip[2], [0.0, 0.0, 1.0].into())];
+
+    if clip.iter().any(|&v| 0.0 > v.z() + v.w() || 0.0 > v.w() - v.z() || 1e-6 > v.w()) {
+        polygon = clip_polygon(polygon, |v| v.z() + v.w());
+        polygon = clip_polygon(polygon, |v| v.w() - v.z());
+        polygon = clip_polygon(polygon, |v| v.w() - 1e-6);
+    }
+
+    for i in 1..polygon.len().saturating_sub(1) {
+        rasterize_clipped([polygon[0], polygon[i], polygon[i + 1]], width, height, front_facing, samples, &mut f);
+    }
+}
+
+fn clip_polygon<D: Fn(&Vector4<f32>) -> f32>(polygon: Vec<(Vector4<f32>, Vector3<f32>)>, distance: D) -> Vec<(Vector4<f32>, Vector3<f32>)> {
+    let mut result = Vec::with_capacity(polygon.len() + 2);
+
+    for i in 0..polygon.len() {
+        let (p0, b0) = polygon[i];
+        let (p1, b1) = polygon[(i + 1) % polygon.len()];
+
+        let d0 = distance(&p0);
+        let d1 = distance(&p1);
+
+        if 0.0 <= d0 {
+            result.push((p0, b0));
+        }
+
+        if (0.0 <= d0) != (0.0 <= d1) {
+            let t = d0 / (d0 - d1);
+            result.push((p0 + (p1 - p0).scale(t), b0 + (b1 - b0).scale(t)));
+        }
+    }
+
+    result
+}
+
+fn rasterize_clipped<F: FnMut(&Fragment, &[f32])>(mut vertices: [(Vector4<f32>, Vector3<f32>); 3], width: u16, height: u16, front_facing: bool, samples: &[Vector2<f32>], f: &mut F) {
+    let screen = |v: &Vector4<f32>| -> Vector3<f32> { [(v.x() / v.w() + 1.0) * 0.5 * width as f32, (v.y() / v.w() + 1.0) * 0.5 * height as f32, (v.z() / v.w()) * 0.5 + 0.5].into() };
+
+    let mut s = [screen(&vertices[0].0), screen(&vertices[1].0), screen(&vertices[2].0)];
+
+    let edge = |a: &Vector3<f32>, b: &Vector3<f32>, x: f32, y: f32| (b.x() - a.x()) * (y - a.y()) - (b.y() - a.y()) * (x - a.x());
+
+    let mut area = edge(&s[0], &s[1], s[2].x(), s[2].y());
+    if 0.0 == area || !area.is_finite() {
+        return;
+    }
+
+    if 0.0 > area {
+        s.swap(1, 2);
+        vertices.swap(1, 2);
+        area = -area;
+    }
+
+    let top_left = |a: &Vector3<f32>, b: &Vector3<f32>| (a.y() == b.y() && b.x() < a.x()) || b.y() < a.y();
+    let biases = [top_left(&s[1], &s[2]), top_left(&s[2], &s[0]), top_left(&s[0], &s[1])];
+
+    let inv_w = [1.0 / vertices[0].0.w(), 1.0 / vertices[1].0.w(), 1.0 / vertices[2].0.w()];
+
+    let min_x = s.iter().map(|&v| v.x()).fold(f32::MAX, f32::min).floor().max(0.0) as i32;
+    let max_x = s.iter().map(|&v| v.x()).fold(f32::MIN, f32::max).ceil().min(width as f32 - 1.0) as i32;
+    let min_y = s.iter().map(|&v| v.y()).fold(f32::MAX, f32::min).floor().max(0.0) as i32;
+    let max_y = s.iter().map(|&v| v.y()).fold(f32::MIN, f32::max).ceil().min(height as f32 - 1.0) as i32;
+
+    let inside = |e: &[f32; 3]| (0..3).all(|i| 0.0 < e[i] || (0.0 == e[i] && biases[i]));
+
+    let mut depths = [0.0; MAX_SAMPLES];
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let mut coverage = 0;
+
+            for (i, sample) in samples.iter().enumerate() {
+                let (sx, sy) = (x as f32 + sample.x(), y as f32 + sample.y());
+                let e = [edge(&s[1], &s[2], sx, sy), edge(&s[2], &s[0], sx, sy), edge(&s[0], &s[1], sx, sy)];
+
+                if inside(&e) {
+                    coverage |= 1 << i;
+                    depths[i] = (e[0] * s[0].z() + e[1] * s[1].z() + e[2] * s[2].z()) / area;
+                }
+            }
+
+            if 0 == coverage {
+                continue;
+            }
+
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let e = [edge(&s[1], &s[2], px, py), edge(&s[2], &s[0], px, py), edge(&s[0], &s[1], px, py)];
+
+            let l = [e[0] / area, e[1] / area, e[2] / area];
+
+            let depth = l[0] * s[0].z() + l[1] * s[1].z() + l[2] * s[2].z();
+
+            let q = [l[0] * inv_w[0], l[1] * inv_w[1], l[2] * inv_w[2]];
+            let sum = q[0] + q[1] + q[2];
+
+            let bar = vertices[0].1.scale(q[0] / sum) + vertices[1].1.scale(q[1] / sum) + vertices[2].1.scale(q[2] / sum);
+
+            f(&Fragment { x, y, depth, bar, front_facing, coverage }, &depths[..samples.len()]);
+        }
+    }
+}
+
+/// Rasterizes one triangle with depth testing and shading, `clip` usually comes from `Shader::vertex`.
+pub fn triangle<S: Shader, I: Image>(clip: &[Vector4<f32>; 3], shader: &mut S, image: &mut I, depth: &mut DepthBuffer, state: &RenderState) {
+    stencil_triangle(clip, shader, image, depth, None, state);
+}
+
+/// Same as `triangle` with the stencil test of `state` run against `stencil`, the stencil state is ignored without a buffer.
+pub fn stencil_triangle<S: Shader, I: Image>(clip: &[Vector4<f32>; 3], shader: &mut S, image: &mut I, depth: &mut DepthBuffer, mut stencil: Option<&mut StencilBuffer>, state: &RenderState) {
+    let front_facing = is_front_facing(clip);
+
+    match state.cull {
+        CullMode::Back if !front_facing => return,
+        CullMode::Front if front_facing => return,
+        _ => {}
+    }
+
+    let stencil_state = state.stencil.as_ref().map(|config| (config, if front_facing { &config.front } else { &config.back }));
+
+    rasterize(clip, image.get_width(), image.get_height(), |fragment| {
+        let coord = [fragment.x as u16, fragment.y as u16].into();
+
+        let mut stencil = match (stencil.as_deref_mut(), stencil_state) {
+            (Some(buffer), Some((config, face))) => Some((buffer, config, face)),
+            _ => None,
+        };
+
+        if let Some((buffer, config, face)) = stencil.as_mut() {
+            if !config.test(face, buffer.get(coord)) {
+                config.update(buffer, coord, face.fail);
+                return;
+            }
+        }
+
+        if !state.depth_test.test(fragment.depth, depth.get(coord)) {
+            if let Some((buffer, config, face)) = stencil.as_mut() {
+                config.update(buffer, coord, face.depth_fail);
+            }
+            return;
+        }
+
+        if let Some(color) = shader.fragment(fragment) {
+            if let Some((buffer, config, face)) = stencil.as_mut() {
+                config.update(buffer, coord, face.pass);
+            }
+
+            if state.depth_write {
+                depth.set(coord, fragment.depth);
+            }
+
+            if !state.color_write {
+                return;
+            }
+
+            match &state.blend {
+                Some(blend) => image.set_color(coord, blend.apply(color, image.get_color(coord))),
+                None => image.set_color(coord, color),
+            }
+        }
+    });
+}
+
+pub fn draw<S: Shader, I: Image>(faces: usize, shader: &mut S, image: &mut I, depth: &mut DepthBuffer, state: &RenderState) {
+    for face in 0..faces {
+        let clip = [shader.vertex(face, 0), shader.vertex(face, 1), shader.vertex(face, 2)];
+
+        triangle(&clip, shader, image, depth, state);
+    }
+}
+
+pub fn draw_stencil<S: Shader, I: Image>(faces: usize, shader: &mut S, image: &mut I, depth: &mut DepthBuffer, stencil: &mut StencilBuffer, state: &RenderState) {
+    for face in 0..faces {
+        let clip = [shader.vertex(face, 0), shader.vertex(face, 1), shader.vertex(face, 2)];
+
+        stencil_triangle(&clip, shader, image, depth, Some(stencil), state);
+    }
+}
+
+/// Same as `draw` with faces ordered back to front by their mean clip space depth, for blended geometry.
+pub fn draw_sorted<S: Shader, I: Image>(faces: usize, shader: &mut S, image: &mut I, depth: &mut DepthBuffer, state: &RenderState) {
+    let mut order: Vec<(usize, f32)> = (0..faces).map(|face| (face, (0..3).map(|nthvert| shader.vertex(face, nthvert).z()).sum())).collect();
+
+    order.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    for (face, _) in order {
+        let clip = [shader.vertex(face, 0), shader.vertex(face, 1), shader.vertex(face, 2)];
+
+        triangle(&clip, shader, image, depth, state);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LineStyle {
+    /// In pixels, widths up to 1 are drawn aliased with `bresenham`.
+    pub width: f32,
+    pub color: TGAColor,
+    /// Subtracted from the line depth before testing it against the depth buffer.
+    pub depth_bias: f32,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            color: [0, 0, 0, 255].into(),
+            depth_bias: 1e-4,
+        }
+    }
+}
+
+/// Clips the segment to the view volume and draws the pixels whose depth along it passes `LessEqual` against `depth`, the depth buffer is not written.
+pub fn line<I: Image>(clip: &[Vector4<f32>; 2], image: &mut I, depth: Option<&DepthBuffer>, style: &LineStyle) {
+    let [mut a, mut b] = *clip;
+
+    let planes: [fn(&Vector4<f32>) -> f32; 3] = [|v| v.z() + v.w(), |v| v.w() - v.z(), |v| v.w() - 1e-6];
+    for distance in planes {
+        let (da, db) = (distance(&a), distance(&b));
+
+        if 0.0 > da && 0.0 > db {
+            return;
+        }
+
+        if 0.0 > da {
+            a = a + (b - a).scale(da / (da - db));
+        } else if 0.0 > db {
+            b = b + (a - b).scale(db / (db - da));
+        }
+    }
+
+    let width = image.get_width();
+    let height = image.get_height();
+
+    let screen = |v: &Vector4<f32>| -> Vector3<f32> { [(v.x() / v.w() + 1.0) * 0.5 * width as f32, (v.y() / v.w() + 1.0) * 0.5 * height as f32, (v.z() / v.w()) * 0.5 + 0.5].into() };
+
+    let s0 = screen(&a);
+    let s1 = screen(&b);
+    let d = s1 - s0;
+    let length2 = d.x() * d.x() + d.y() * d.y();
+
+    let visible = |x: i32, y: i32| -> bool {
+        if 0 > x || 0 > y || x >= width as i32 || y >= height as i32 {
+            return false;
+        }
+
+        let Some(depth) = depth else {
+            return true;
+        };
+
+        let (px, py) = (x as f32 + 0.5 - s0.x(), y as f32 + 0.5 - s0.y());
+        let t = if 0.0 < length2 { ((px * d.x() + py * d.y()) / length2).clamp(0.0, 1.0) } else { 0.0 };
+
+        s0.z() + d.z() * t - style.depth_bias <= depth.get([x as u16, y as u16].into())
+    };
+
+    if 1.0 >= style.width {
+        let p0 = [s0.x().floor() as i32, s0.y().floor() as i32].into();
+        let p1 = [s1.x().floor() as i32, s1.y().floor() as i32].into();
+
+        bresenham(p0, p1, |x, y| {
+            if visible(x, y) {
+                image.blend_pixel(x, y, style.color, 1.0);
+            }
+        });
+    } else {
+        let mut path = Path::new();
+        path.move_to([s0.x(), s0.y()].into());
+        path.line_to([s1.x(), s1.y()].into());
+
+        let stroke = Stroke {
+            width: style.width,
+            cap: LineCap::Round,
+            ..Stroke::default()
+        };
+
+        let clip = (0, 0, image.get_width() as i32, image.get_height() as i32);
+        stroke_path(&path, &stroke, clip, |x, y, coverage| {
+            if visible(x, y) {
+                image.blend_pixel(x, y, style.color, coverage);
+            }
+        });
+    }
+}
@@ -0,0 +1,54 @@
+//! <https://paulbourke.net/dataformats/pic/>
+//! <https://en.wikipedia.org/wiki/RGBE_image_format>
+//!
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::img::Buffer;
+use crate::la::Vector3;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+
+/// Radiance RGBE with flat scanlines, rows are written top down from the bottom-left origin buffer.
+pub fn save<P: AsRef<Path>>(radiance: &Buffer<Vector3<f32>>, path: P) -> Result<(), Box<dyn Error>> {
+    let width = radiance.get_width();
+    let height = radiance.get_height();
+
+    let mut bytes = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width).into_bytes();
+    bytes.reserve(width as usize * height as usize * 4);
+
+    for y in (0..height).rev() {
+        for x in 0..width {
+            bytes.extend_from_slice(&rgbe(radiance.get([x, y].into())));
+        }
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+
+    file.write_all(&bytes)?;
+
+    Ok(())
+}
+
+fn rgbe(c: Vector3<f32>) -> [u8; 4] {
+    let v = c.x().max(c.y()).max(c.z());
+
+    if 1e-32 > v || !v.is_finite() {
+        return [0; 4];
+    }
+
+    let exponent = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f32.powi(exponent);
+
+    let channel = |x: f32| (x.max(0.0) * scale).min(255.0) as u8;
+
+    [channel(c.x()), channel(c.y()), channel(c.z()), (exponent + 128).clamp(0, 255) as u8]
+}
@@ -0,0 +1,145 @@
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector4;
+use crate::la::A;
+use crate::la::B;
+use crate::la::G;
+use crate::la::R;
+use crate::la::X;
+use crate::la::Y;
+use crate::tga::TGAColor;
+use crate::tga::TGACoord;
+use crate::tga::TGAImage;
+
+pub trait Image {
+    fn get_width(&self) -> u16;
+
+    fn get_height(&self) -> u16;
+
+    fn get_color(&self, coord: TGACoord) -> TGAColor;
+
+    fn set_color(&mut self, coord: TGACoord, color: TGAColor);
+
+    /// Composites `color` over the pixel with the alpha of `color` scaled by `alpha`.
+    fn blend_color(&mut self, coord: TGACoord, color: TGAColor, alpha: f32) {
+        let a = (color.a() as f32 / 255.0 * alpha).clamp(0.0, 1.0);
+
+        if 0.0 >= a {
+            return;
+        }
+
+        if 1.0 <= a {
+            self.set_color(coord, color);
+            return;
+        }
+
+        let dst = self.get_color(coord);
+        let mix = |s: u8, d: u8| (s as f32 * a + d as f32 * (1.0 - a) + 0.5) as u8;
+
+        self.set_color(coord, [mix(color.r(), dst.r()), mix(color.g(), dst.g()), mix(color.b(), dst.b()), (255.0 * a + dst.a() as f32 * (1.0 - a) + 0.5) as u8].into());
+    }
+
+    /// Same as `blend_color` with signed coordinates, pixels outside of the image are ignored.
+    fn blend_pixel(&mut self, x: i32, y: i32, color: TGAColor, alpha: f32) {
+        if 0 > x || 0 > y || x >= self.get_width() as i32 || y >= self.get_height() as i32 {
+            return;
+        }
+
+        self.blend_color([x as u16, y as u16].into(), color, alpha);
+    }
+}
+
+impl Image for TGAImage {
+    fn get_width(&self) -> u16 {
+        TGAImage::get_width(self)
+    }
+
+    fn get_height(&self) -> u16 {
+        TGAImage::get_height(self)
+    }
+
+    fn get_color(&self, coord: TGACoord) -> TGAColor {
+        TGAImage::get_color(self, coord)
+    }
+
+    fn set_color(&mut self, coord: TGACoord, color: TGAColor) {
+        TGAImage::set_color(self, coord, color)
+    }
+}
+
+/// Channels scaled to `[0, 1]`.
+pub fn to_float(c: TGAColor) -> Vector4<f32> {
+    [c.r() as f32 / 255.0, c.g() as f32 / 255.0, c.b() as f32 / 255.0, c.a() as f32 / 255.0].into()
+}
+
+/// Bilinear lookup with repeating wrap, `uv` of `[0, 0]` is the bottom left corner of the image, channels are scaled to `[0, 1]`.
+pub fn sample<I: Image>(image: &I, uv: Vector2<f32>) -> Vector4<f32> {
+    let width = image.get_width() as i32;
+    let height = image.get_height() as i32;
+
+    let x = uv.x().rem_euclid(1.0) * width as f32 - 0.5;
+    let y = uv.y().rem_euclid(1.0) * height as f32 - 0.5;
+
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let tx = x - x0;
+    let ty = y - y0;
+
+    let texel = |x: i32, y: i32| to_float(image.get_color([x.rem_euclid(width) as u16, y.rem_euclid(height) as u16].into()));
+
+    let (x0, y0) = (x0 as i32, y0 as i32);
+
+    let bottom = texel(x0, y0).scale(1.0 - tx) + texel(x0 + 1, y0).scale(tx);
+    let top = texel(x0, y0 + 1).scale(1.0 - tx) + texel(x0 + 1, y0 + 1).scale(tx);
+
+    bottom.scale(1.0 - ty) + top.scale(ty)
+}
+
+#[derive(Clone, Debug)]
+pub struct Buffer<T> {
+    width: u16,
+    height: u16,
+    data: Vec<T>,
+}
+
+impl<T: Copy> Buffer<T> {
+    pub fn new(width: u16, height: u16, value: T) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![value; width as usize * height as usize],
+        }
+    }
+
+    pub fn get_width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn get(&self, coord: TGACoord) -> T {
+        self.data[coord.x() as usize + coord.y() as usize * self.width as usize]
+    }
+
+    pub fn set(&mut self, coord: TGACoord, value: T) {
+        self.data[coord.x() as usize + coord.y() as usize * self.width as usize] = value;
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.data.fill(value);
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+}
+
+pub type DepthBuffer = Buffer<f32>;
+
+pub type StencilBuffer = Buffer<u8>;
@@ -120,6 +120,22 @@ pub trait TransposeAssign {
     fn transpose_assign(&mut self);
 }
 
+pub trait Hadamard<Rhs = Self> {
+    type Output;
+
+    fn hadamard(self, rhs: Rhs) -> Self::Output;
+}
+
+pub trait Identity {
+    fn identity() -> Self;
+}
+
+pub trait Inverse {
+    type Output;
+
+    fn inverse(self) -> Self::Output;
+}
+
 macro_rules! impl_vector_xyzw_rgba {
     ($t0:ty, $i0:ident, $t1:ty, $t2:ty, $l0:literal) => {
         impl $t0 for $t1 {
@@ -312,6 +328,65 @@ macro_rules! impl_vector_normal {
     };
 }
 
+macro_rules! impl_matrix_inverse {
+    ($($t0:ty),+$(,)?) => {
+        $(
+            impl<const N: usize> Inverse for &Matrix<$t0, N, N> {
+                type Output = Option<Matrix<$t0, N, N>>;
+
+                fn inverse(self) -> Self::Output {
+                    let mut lhs = self.0;
+                    let mut result = Matrix::<$t0, N, N>::identity().0;
+
+                    for c in 0..N {
+                        let mut pivot = c;
+                        for r in c + 1..N {
+                            if lhs[r][c].abs() > lhs[pivot][c].abs() {
+                                pivot = r;
+                            }
+                        }
+
+                        if <$t0>::EPSILON > lhs[pivot][c].abs() {
+                            return None;
+                        }
+
+                        lhs.swap(c, pivot);
+                        result.swap(c, pivot);
+
+                        let factor = 1 as $t0 / lhs[c][c];
+                        for i in 0..N {
+                            lhs[c][i] *= factor;
+                            result[c][i] *= factor;
+                        }
+
+                        for r in 0..N {
+                            if r == c {
+                                continue;
+                            }
+
+                            let factor = lhs[r][c];
+                            for i in 0..N {
+                                lhs[r][i] -= factor * lhs[c][i];
+                                result[r][i] -= factor * result[c][i];
+                            }
+                        }
+                    }
+
+                    Some(Matrix(result))
+                }
+            }
+
+            impl<const N: usize> Inverse for Matrix<$t0, N, N> {
+                type Output = Option<Self>;
+
+                fn inverse(self) -> Self::Output {
+                    (&self).inverse()
+                }
+            }
+        )+
+    };
+}
+
 macro_rules! impl_vector_from_vector {
     ($t0:ty, $t1:ty, $t2:ty, $l0:literal, $l1:literal) => {
         impl From<$t0> for $t1 {
@@ -598,6 +673,44 @@ macro_rules! impl_matrix {
                 }
             }
 
+            // impl Hadamard
+            impl<const ROW: usize, const COLUMN: usize> Hadamard for &Matrix<$t0, ROW, COLUMN> {
+                type Output = Matrix<$t0, ROW, COLUMN>;
+
+                fn hadamard(self, rhs: Self) -> Self::Output {
+                    let mut result = Matrix([[0 as $t0; COLUMN]; ROW]);
+
+                    for r in 0..ROW {
+                        for c in 0..COLUMN {
+                            result.0[r][c] = self.0[r][c] * rhs.0[r][c];
+                        }
+                    }
+
+                    result
+                }
+            }
+
+            impl<const ROW: usize, const COLUMN: usize> Hadamard for Matrix<$t0, ROW, COLUMN> {
+                type Output = Self;
+
+                fn hadamard(self, rhs: Self) -> Self::Output {
+                    (&self).hadamard(&rhs)
+                }
+            }
+
+            // impl Identity
+            impl<const N: usize> Identity for Matrix<$t0, N, N> {
+                fn identity() -> Self {
+                    let mut result = Matrix([[0 as $t0; N]; N]);
+
+                    for i in 0..N {
+                        result.0[i][i] = 1 as $t0;
+                    }
+
+                    result
+                }
+            }
+
             // impl Deref, DerefMut
             impl<const ROW: usize, const COLUMN: usize> Deref for Matrix<$t0, ROW, COLUMN> {
                 type Target = [[$t0; COLUMN]; ROW];
@@ -662,3 +775,107 @@ impl_vector_dot!(f32, f64,);
 impl_vector_cross!(f32, f64,);
 impl_vector_magnitude!(f32, f64,);
 impl_vector_normal!(f32, f64,);
+impl_matrix_inverse!(f32, f64,);
+
+/// Axis-aligned bounding box, the default is empty with `min` above `max`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self {
+            min: f32::INFINITY.into(),
+            max: f32::NEG_INFINITY.into(),
+        }
+    }
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: &[Vector3<f32>]) -> Self {
+        points.iter().fold(Self::default(), |bounds, &p| bounds.grow(p))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[0][i] > self.max[0][i])
+    }
+
+    pub fn grow(&self, p: Vector3<f32>) -> Self {
+        Self {
+            min: [0, 1, 2].map(|i| self.min[0][i].min(p[0][i])).into(),
+            max: [0, 1, 2].map(|i| self.max[0][i].max(p[0][i])).into(),
+        }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: [0, 1, 2].map(|i| self.min[0][i].min(other.min[0][i])).into(),
+            max: [0, 1, 2].map(|i| self.max[0][i].max(other.max[0][i])).into(),
+        }
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max).scale(0.5)
+    }
+
+    pub fn extent(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// Zero for empty boxes.
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+
+        let d = self.extent();
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    /// Index of the longest side.
+    pub fn largest_axis(&self) -> usize {
+        let d = self.extent();
+
+        if d.x() >= d.y() && d.x() >= d.z() {
+            0
+        } else if d.y() >= d.z() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn contains(&self, p: Vector3<f32>) -> bool {
+        (0..3).all(|i| self.min[0][i] <= p[0][i] && p[0][i] <= self.max[0][i])
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        (0..3).all(|i| self.min[0][i] <= other.max[0][i] && other.min[0][i] <= self.max[0][i])
+    }
+
+    /// Slab test, the entry distance when the ray starting at `origin` enters the box before `t_max`, `inverse_direction` is the componentwise reciprocal of the direction.
+    pub fn intersect_ray(&self, origin: Vector3<f32>, inverse_direction: Vector3<f32>, t_max: f32) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = t_max;
+
+        for i in 0..3 {
+            let t0 = (self.min[0][i] - origin[0][i]) * inverse_direction[0][i];
+            let t1 = (self.max[0][i] - origin[0][i]) * inverse_direction[0][i];
+
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+
+        if near <= far {
+            Some(near)
+        } else {
+            None
+        }
+    }
+}
@@ -1,7 +1,33 @@
-#![feature(generic_arg_infer)]
 #![allow(non_upper_case_globals)]
 
+pub mod bvh;
 pub mod cg;
+pub mod cubemap;
+pub mod deferred;
+pub mod dof;
+pub mod fog;
+pub mod font;
+pub mod gl;
+pub mod hdr;
+pub mod img;
 pub mod la;
+pub mod light;
+pub mod motion;
+pub mod msaa;
+pub mod mtl;
 pub mod obj;
+pub mod oit;
+pub mod path;
+pub mod pbr;
+pub mod post;
+pub mod ray;
+pub mod rng;
+pub mod shader;
+pub mod shadow;
+pub mod sky;
+pub mod ssao;
 pub mod tga;
+pub mod toon;
+pub mod ttf;
+pub mod volume;
+pub mod wireframe;
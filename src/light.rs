@@ -0,0 +1,192 @@
+//! <https://learnopengl.com/Lighting/Light-casters>
+//!
+//!
+
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Magnitude;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self {
+            constant: 1.0,
+            linear: 0.09,
+            quadratic: 0.032,
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn factor(&self, distance: f32) -> f32 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance).max(1e-6)
+    }
+}
+
+/// `direction` is where the light travels to, cone angles are half angles in radians and area lights span `position ± u / 2 ± v / 2`, emitting towards `u × v`.
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+    Directional {
+        direction: Vector3<f32>,
+        color: Vector3<f32>,
+        intensity: f32,
+    },
+    Point {
+        position: Vector3<f32>,
+        color: Vector3<f32>,
+        intensity: f32,
+        attenuation: Attenuation,
+    },
+    Spot {
+        position: Vector3<f32>,
+        direction: Vector3<f32>,
+        inner: f32,
+        outer: f32,
+        color: Vector3<f32>,
+        intensity: f32,
+        attenuation: Attenuation,
+    },
+    Area {
+        position: Vector3<f32>,
+        u: Vector3<f32>,
+        v: Vector3<f32>,
+        color: Vector3<f32>,
+        intensity: f32,
+        samples: u32,
+    },
+}
+
+/// Unit vector from the lit point towards the light, the incoming radiance and the distance to the light.
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    pub direction: Vector3<f32>,
+    pub radiance: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Light {
+    pub fn directional(direction: Vector3<f32>) -> Self {
+        Light::Directional {
+            direction: direction.normal(),
+            color: 1.0.into(),
+            intensity: 1.0,
+        }
+    }
+
+    /// Distance from `position` beyond which the radiance stays below `cutoff`, `None` for lights without falloff.
+    pub fn range(&self, cutoff: f32) -> Option<f32> {
+        let solve = |intensity: f32, a: &Attenuation| {
+            let c = a.constant - intensity / cutoff;
+            if 0.0 < a.quadratic {
+                Some((-a.linear + (a.linear * a.linear - 4.0 * a.quadratic * c).max(0.0).sqrt()) / (2.0 * a.quadratic))
+            } else if 0.0 < a.linear {
+                Some((-c / a.linear).max(0.0))
+            } else {
+                None
+            }
+        };
+
+        let peak = |color: Vector3<f32>, intensity: f32| intensity * color[0][0].max(color[0][1]).max(color[0][2]);
+
+        match *self {
+            Light::Directional { .. } => None,
+            Light::Point { color, intensity, attenuation, .. } | Light::Spot { color, intensity, attenuation, .. } => solve(peak(color, intensity), &attenuation),
+            Light::Area { u, v, color, intensity, .. } => Some((peak(color, intensity) / cutoff).max(0.0).sqrt() + (u + v).magnitude().max((u - v).magnitude()) * 0.5),
+        }
+    }
+
+    /// Calls `f` once per sample reaching `p`, area lights are split into `samples` x `samples` jittered strata.
+    /// Samples whose light position coincides with `p` are skipped.
+    pub fn illuminate<F: FnMut(&LightSample)>(&self, p: Vector3<f32>, mut f: F) {
+        match *self {
+            Light::Directional { direction, color, intensity } => f(&LightSample {
+                direction: direction.normal().scale(-1.0),
+                radiance: color.scale(intensity),
+                distance: f32::INFINITY,
+            }),
+            Light::Point { position, color, intensity, attenuation } => {
+                let d = position - p;
+                let distance = d.magnitude();
+                if 0.0 >= distance {
+                    return;
+                }
+
+                f(&LightSample {
+                    direction: d.scale(1.0 / distance),
+                    radiance: color.scale(intensity * attenuation.factor(distance)),
+                    distance,
+                });
+            }
+            Light::Spot {
+                position,
+                direction,
+                inner,
+                outer,
+                color,
+                intensity,
+                attenuation,
+            } => {
+                let d = position - p;
+                let distance = d.magnitude();
+                if 0.0 >= distance {
+                    return;
+                }
+                let l = d.scale(1.0 / distance);
+
+                let cos = l.scale(-1.0).dot(direction.normal());
+                let (cos_inner, cos_outer) = (inner.cos(), outer.cos());
+                let t = ((cos - cos_outer) / (cos_inner - cos_outer).max(1e-6)).clamp(0.0, 1.0);
+                let cone = t * t * (3.0 - 2.0 * t);
+
+                if 0.0 < cone {
+                    f(&LightSample {
+                        direction: l,
+                        radiance: color.scale(intensity * attenuation.factor(distance) * cone),
+                        distance,
+                    });
+                }
+            }
+            Light::Area { position, u, v, color, intensity, samples } => {
+                let n = u.cross(v).normal();
+                let samples = samples.max(1);
+                let weight = intensity / (samples * samples) as f32;
+                let mut rng = Rng::new(p[0][0].to_bits() as u64 ^ ((p[0][1].to_bits() as u64) << 21) ^ ((p[0][2].to_bits() as u64) << 42));
+
+                for j in 0..samples {
+                    for i in 0..samples {
+                        let s = (i as f32 + rng.next_f32()) / samples as f32 - 0.5;
+                        let t = (j as f32 + rng.next_f32()) / samples as f32 - 0.5;
+
+                        let d = position + u.scale(s) + v.scale(t) - p;
+                        let distance = d.magnitude();
+                        if 0.0 >= distance {
+                            continue;
+                        }
+                        let l = d.scale(1.0 / distance);
+
+                        let cos = l.scale(-1.0).dot(n);
+                        if 0.0 >= cos {
+                            continue;
+                        }
+
+                        f(&LightSample {
+                            direction: l,
+                            radiance: color.scale(weight * cos / (distance * distance).max(1e-6)),
+                            distance,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,3 @@
-#![feature(generic_arg_infer)]
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
@@ -11,9 +10,13 @@ use std::ops::Deref;
 use std::path::Path;
 
 use rs_tinyrenderer::cg::bresenham;
+use rs_tinyrenderer::gl::draw;
+use rs_tinyrenderer::gl::RenderState;
+use rs_tinyrenderer::img::DepthBuffer;
 use rs_tinyrenderer::la::Cross;
 use rs_tinyrenderer::la::CrossAssign;
 use rs_tinyrenderer::la::Dot;
+use rs_tinyrenderer::la::Identity;
 use rs_tinyrenderer::la::Magnitude;
 use rs_tinyrenderer::la::Matrix;
 use rs_tinyrenderer::la::NormalAssign;
@@ -28,14 +31,24 @@ use rs_tinyrenderer::la::W;
 use rs_tinyrenderer::la::X;
 use rs_tinyrenderer::la::Y;
 use rs_tinyrenderer::la::Z;
+use rs_tinyrenderer::light::Light;
 use rs_tinyrenderer::obj::Obj;
+use rs_tinyrenderer::shader::render;
+use rs_tinyrenderer::shader::Camera;
+use rs_tinyrenderer::shader::LightingShader;
+use rs_tinyrenderer::shader::Material;
+use rs_tinyrenderer::shader::ShadingModel;
+use rs_tinyrenderer::shader::Uniforms;
 use rs_tinyrenderer::tga::TGACoord;
 use rs_tinyrenderer::tga::TGAImage;
 use rs_tinyrenderer::tga::RED;
 use rs_tinyrenderer::tga::WHITE;
+use rs_tinyrenderer::wireframe::WireframeShader;
 
 fn main() -> Result<(), Box<dyn Error>> {
     african_head("./african_head.obj", "./66666.tga")?;
+    african_head_lit("./african_head.obj", "./77777.tga")?;
+    african_head_wireframe("./african_head.obj", "./88888.tga")?;
 
     Ok(())
 }
@@ -72,3 +85,32 @@ fn african_head<P: AsRef<Path>>(obj_file: P, tga_file: P) -> Result<(), Box<dyn
 
     Ok(())
 }
+
+fn african_head_lit<P: AsRef<Path>>(obj_file: P, tga_file: P) -> Result<(), Box<dyn Error>> {
+    let obj = Obj::load(obj_file)?;
+
+    let mut img = TGAImage::new(1024, 1024);
+
+    render(&obj, &mut img, &Camera::default(), [1.0, 1.0, 1.0].into(), ShadingModel::BlinnPhong, &Material::default());
+
+    img.save(tga_file)?;
+
+    Ok(())
+}
+
+fn african_head_wireframe<P: AsRef<Path>>(obj_file: P, tga_file: P) -> Result<(), Box<dyn Error>> {
+    let obj = Obj::load(obj_file)?;
+
+    let mut img = TGAImage::new(1024, 1024);
+    let mut depth = DepthBuffer::new(1024, 1024, f32::MAX);
+
+    let uniforms = Uniforms::new(Matrix::identity(), &Camera::default(), 1.0, &[Light::directional([-1.0, -1.0, -1.0].into())]);
+    let shader = LightingShader::new(&obj, uniforms, Material::default(), ShadingModel::BlinnPhong);
+    let mut shader = WireframeShader::new(shader, 1.0, RED.into(), [1024.0, 1024.0].into());
+
+    draw(obj.fs.len(), &mut shader, &mut img, &mut depth, &RenderState::default());
+
+    img.save(tga_file)?;
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! <https://developer.nvidia.com/gpugems/gpugems3/part-iv-image-effects/chapter-27-motion-blur-post-processing-effect>
+//!
+
+use crate::gl::embed;
+use crate::gl::is_front_facing;
+use crate::gl::rasterize;
+use crate::gl::CullMode;
+use crate::gl::RenderState;
+use crate::img::Buffer;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Matrix;
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::obj::Obj;
+use crate::post::sample_pixel;
+use crate::post::Pass;
+use crate::shader::to_rgba;
+use crate::tga::TGAImage;
+
+/// Transforms of the current and the previous frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Motion {
+    pub model: Matrix<f32, 4, 4>,
+    pub previous_model: Matrix<f32, 4, 4>,
+    pub view_projection: Matrix<f32, 4, 4>,
+    pub previous_view_projection: Matrix<f32, 4, 4>,
+}
+
+/// Screen space movement in pixels since the previous frame of the closest surface.
+#[derive(Clone, Debug)]
+pub struct VelocityBuffer {
+    pub velocity: Buffer<Vector2<f32>>,
+    pub depth: DepthBuffer,
+}
+
+impl VelocityBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            velocity: Buffer::new(width, height, 0.0.into()),
+            depth: DepthBuffer::new(width, height, f32::MAX),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.velocity.fill(0.0.into());
+        self.depth.fill(f32::MAX);
+    }
+
+    pub fn draw(&mut self, obj: &Obj, motion: &Motion, state: &RenderState) {
+        let width = self.velocity.get_width();
+        let height = self.velocity.get_height();
+
+        let screen = |clip: Vector4<f32>| -> Vector2<f32> { [(clip.x() / clip.w() + 1.0) * 0.5 * width as f32, (clip.y() / clip.w() + 1.0) * 0.5 * height as f32].into() };
+
+        for face in 0..obj.fs.len() {
+            let vertices = [0, 1, 2].map(|nthvert| embed(obj.vert(face, nthvert), 1.0));
+            let clip = vertices.map(|v| v * motion.model * motion.view_projection);
+            let previous = vertices.map(|v| v * motion.previous_model * motion.previous_view_projection);
+
+            match state.cull {
+                CullMode::Back if !is_front_facing(&clip) => continue,
+                CullMode::Front if is_front_facing(&clip) => continue,
+                _ => {}
+            }
+
+            rasterize(&clip, width, height, |fragment| {
+                let coord = [fragment.x as u16, fragment.y as u16].into();
+
+                if !state.depth_test.test(fragment.depth, self.depth.get(coord)) {
+                    return;
+                }
+
+                if state.depth_write {
+                    self.depth.set(coord, fragment.depth);
+                }
+
+                let bar = fragment.bar;
+                let now = clip[0].scale(bar.x()) + clip[1].scale(bar.y()) + clip[2].scale(bar.z());
+                let before = previous[0].scale(bar.x()) + previous[1].scale(bar.y()) + previous[2].scale(bar.z());
+
+                let velocity = if 1e-6 < before.w() { screen(now) - screen(before) } else { 0.0.into() };
+                self.velocity.set(coord, velocity);
+            });
+        }
+    }
+}
+
+/// Averages `samples` taps along the velocity of every pixel, `scale` is the shutter fraction of the frame interval.
+#[derive(Clone, Copy, Debug)]
+pub struct MotionBlur<'a> {
+    pub velocity: &'a Buffer<Vector2<f32>>,
+    pub samples: u32,
+    pub scale: f32,
+    /// Longest blur in pixels.
+    pub max_length: f32,
+}
+
+impl<'a> MotionBlur<'a> {
+    pub fn new(velocity: &'a Buffer<Vector2<f32>>) -> Self {
+        Self {
+            velocity,
+            samples: 16,
+            scale: 1.0,
+            max_length: 32.0,
+        }
+    }
+}
+
+impl Pass for MotionBlur<'_> {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let width = source.get_width().min(self.velocity.get_width());
+        let height = source.get_height().min(self.velocity.get_height());
+
+        let samples = self.samples.max(1);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut v = self.velocity.get([x, y].into()).scale(self.scale);
+
+                let length = (v.x() * v.x() + v.y() * v.y()).sqrt();
+                if 0.5 > length {
+                    target.set_color([x, y].into(), source.get_color([x, y].into()));
+                    continue;
+                }
+
+                if length > self.max_length {
+                    v = v.scale(self.max_length / length);
+                }
+
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                let mut sum = Vector4::from(0.0);
+                for i in 0..samples {
+                    let t = (i as f32 + 0.5) / samples as f32 - 0.5;
+                    sum += sample_pixel(source, px - v.x() * t, py - v.y() * t);
+                }
+
+                let color = sum.scale(1.0 / samples as f32);
+                target.set_color([x, y].into(), to_rgba(Vector3::from(color), color[0][3]));
+            }
+        }
+    }
+}
+
+/// Averages whole frames, rendering sub-frames at times spread over the shutter interval gives reference motion blur.
+#[derive(Clone, Debug)]
+pub struct Accumulator {
+    sum: Buffer<Vector4<f32>>,
+    count: u32,
+}
+
+impl Accumulator {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            sum: Buffer::new(width, height, 0.0.into()),
+            count: 0,
+        }
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn add(&mut self, image: &dyn Image) {
+        for y in 0..self.sum.get_height().min(image.get_height()) {
+            for x in 0..self.sum.get_width().min(image.get_width()) {
+                let c = image.get_color([x, y].into());
+                let sum = self.sum.get([x, y].into()) + Vector4::from([c[0][0] as f32, c[0][1] as f32, c[0][2] as f32, c[0][3] as f32]);
+                self.sum.set([x, y].into(), sum);
+            }
+        }
+
+        self.count += 1;
+    }
+
+    pub fn resolve(&self) -> TGAImage {
+        let mut image = TGAImage::new(self.sum.get_width(), self.sum.get_height());
+        let count = self.count.max(1) as f32;
+
+        for y in 0..self.sum.get_height() {
+            for x in 0..self.sum.get_width() {
+                let c = self.sum.get([x, y].into()).scale(1.0 / count);
+                image.set_color([x, y].into(), [0, 1, 2, 3].map(|i| (c[0][i] + 0.5).min(255.0) as u8).into());
+            }
+        }
+
+        image
+    }
+}
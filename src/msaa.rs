@@ -0,0 +1,174 @@
+//! <https://learn.microsoft.com/en-us/windows/win32/api/d3d11/ne-d3d11-d3d11_standard_multisample_quality_levels>
+//! <https://en.wikipedia.org/wiki/Multisample_anti-aliasing>
+//!
+
+use crate::gl::is_front_facing;
+use crate::gl::rasterize_samples;
+use crate::gl::CullMode;
+use crate::gl::RenderState;
+use crate::gl::Shader;
+use crate::img::Image;
+use crate::la::Vector2;
+use crate::tga::TGAColor;
+use crate::tga::TGAImage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleCount {
+    X2,
+    X4,
+    X8,
+}
+
+impl SampleCount {
+    pub fn count(self) -> usize {
+        match self {
+            SampleCount::X2 => 2,
+            SampleCount::X4 => 4,
+            SampleCount::X8 => 8,
+        }
+    }
+
+    /// Standard Direct3D sample positions mirrored vertically for the bottom left image origin, as offsets inside the pixel.
+    pub fn positions(self) -> Vec<Vector2<f32>> {
+        let pattern: &[(i32, i32)] = match self {
+            SampleCount::X2 => &[(4, 4), (-4, -4)],
+            SampleCount::X4 => &[(-2, -6), (6, -2), (-6, 2), (2, 6)],
+            SampleCount::X8 => &[(1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7)],
+        };
+
+        pattern.iter().map(|&(x, y)| [0.5 + x as f32 / 16.0, 0.5 - y as f32 / 16.0].into()).collect()
+    }
+}
+
+/// Color and depth per sample, shaded once per pixel and resolved by averaging.
+pub struct MultisampleTarget {
+    width: u16,
+    height: u16,
+    positions: Vec<Vector2<f32>>,
+    color: Vec<TGAColor>,
+    depth: Vec<f32>,
+}
+
+impl MultisampleTarget {
+    pub fn new(width: u16, height: u16, samples: SampleCount) -> Self {
+        let count = width as usize * height as usize * samples.count();
+
+        Self {
+            width,
+            height,
+            positions: samples.positions(),
+            color: vec![[0, 0, 0, 0].into(); count],
+            depth: vec![f32::MAX; count],
+        }
+    }
+
+    pub fn get_width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn clear(&mut self, color: TGAColor) {
+        self.color.fill(color);
+        self.depth.fill(f32::MAX);
+    }
+
+    /// Depth tests every covered sample and shades the pixel once when any of them passes, stencil state is ignored.
+    pub fn draw<S: Shader>(&mut self, faces: usize, shader: &mut S, state: &RenderState) {
+        let count = self.positions.len();
+        let width = self.width as usize;
+
+        for face in 0..faces {
+            let clip = [shader.vertex(face, 0), shader.vertex(face, 1), shader.vertex(face, 2)];
+
+            match state.cull {
+                CullMode::Back if !is_front_facing(&clip) => continue,
+                CullMode::Front if is_front_facing(&clip) => continue,
+                _ => {}
+            }
+
+            rasterize_samples(&clip, self.width, self.height, &self.positions, |fragment, depths| {
+                let base = (fragment.x as usize + fragment.y as usize * width) * count;
+
+                let passed = (0..count).filter(|&i| 0 != fragment.coverage & 1 << i && state.depth_test.test(depths[i], self.depth[base + i])).fold(0u32, |mask, i| mask | 1 << i);
+                if 0 == passed {
+                    return;
+                }
+
+                let Some(color) = shader.fragment(fragment) else {
+                    return;
+                };
+
+                for i in (0..count).filter(|&i| 0 != passed & 1 << i) {
+                    if state.depth_write {
+                        self.depth[base + i] = depths[i];
+                    }
+
+                    if state.color_write {
+                        self.color[base + i] = match &state.blend {
+                            Some(blend) => blend.apply(color, self.color[base + i]),
+                            None => color,
+                        };
+                    }
+                }
+            });
+        }
+    }
+
+    /// Box filters the samples of every pixel into `image`.
+    pub fn resolve_into<I: Image>(&self, image: &mut I) {
+        let count = self.positions.len();
+
+        for y in 0..self.height.min(image.get_height()) {
+            for x in 0..self.width.min(image.get_width()) {
+                let base = (x as usize + y as usize * self.width as usize) * count;
+
+                let mut sum = [0u32; 4];
+                for color in &self.color[base..base + count] {
+                    for (c, s) in sum.iter_mut().enumerate() {
+                        *s += color[0][c] as u32;
+                    }
+                }
+
+                image.set_color([x, y].into(), sum.map(|s| ((s + count as u32 / 2) / count as u32) as u8).into());
+            }
+        }
+    }
+
+    pub fn resolve(&self) -> TGAImage {
+        let mut image = TGAImage::new(self.width, self.height);
+        self.resolve_into(&mut image);
+        image
+    }
+}
+
+/// Box filters `factor` x `factor` blocks, rendering at `factor` times the size and downsampling gives supersampling.
+pub fn downsample<I: Image>(image: &I, factor: u16) -> TGAImage {
+    let factor = factor.max(1);
+    let width = image.get_width() / factor;
+    let height = image.get_height() / factor;
+    let count = factor as u32 * factor as u32;
+
+    let mut result = TGAImage::new(width, height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+
+            for sy in 0..factor {
+                for sx in 0..factor {
+                    let color = image.get_color([x * factor + sx, y * factor + sy].into());
+                    for (c, s) in sum.iter_mut().enumerate() {
+                        *s += color[0][c] as u32;
+                    }
+                }
+            }
+
+            result.set_color([x, y].into(), sum.map(|s| ((s + count / 2) / count) as u8).into());
+        }
+    }
+
+    result
+}
@@ -0,0 +1,117 @@
+//! <http://paulbourke.net/dataformats/mtl/>
+//!
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::Path;
+
+use crate::la::Vector3;
+use crate::shader::Material;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mtl {
+    pub name: String,
+    pub ka: Vector3<f32>,
+    pub kd: Vector3<f32>,
+    pub ks: Vector3<f32>,
+    /// Emitted radiance.
+    pub ke: Vector3<f32>,
+    pub ns: f32,
+    /// Dissolve, 1 is opaque.
+    pub d: f32,
+    /// Index of refraction.
+    pub ni: f32,
+    pub illum: u32,
+    pub map_kd: Option<String>,
+}
+
+impl Mtl {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ka: 0.0.into(),
+            kd: 0.8.into(),
+            ks: 0.0.into(),
+            ke: 0.0.into(),
+            ns: 1.0,
+            d: 1.0,
+            ni: 1.0,
+            illum: 2,
+            map_kd: None,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, Box<dyn Error>> {
+        let mut file = OpenOptions::new().read(true).open(path)?;
+
+        let mut content = String::new();
+
+        file.read_to_string(&mut content)?;
+
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let mut mtls: Vec<Self> = Vec::new();
+
+        for line in content.lines() {
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+
+            if "newmtl" == keyword {
+                mtls.push(Self::new(words.next().ok_or("材质名缺失")?));
+                continue;
+            }
+
+            if keyword.starts_with('#') {
+                continue;
+            }
+
+            let Some(mtl) = mtls.last_mut() else {
+                return Err("材质定义前缺少 newmtl".into());
+            };
+
+            let ds: Vec<f32> = match keyword {
+                "Ka" | "Kd" | "Ks" | "Ke" | "Ns" | "d" | "Tr" | "Ni" => words.clone().take(3).map(|w| w.parse()).collect::<Result<_, _>>()?,
+                _ => Vec::new(),
+            };
+            let color = || -> Result<Vector3<f32>, Box<dyn Error>> {
+                match ds.len() {
+                    0 => Err("材质数据解释失败".into()),
+                    1 | 2 => Ok(ds[0].into()),
+                    _ => Ok([ds[0], ds[1], ds[2]].into()),
+                }
+            };
+            let scalar = || -> Result<f32, Box<dyn Error>> { ds.first().copied().ok_or_else(|| "材质数据解释失败".into()) };
+
+            match keyword {
+                "Ka" => mtl.ka = color()?,
+                "Kd" => mtl.kd = color()?,
+                "Ks" => mtl.ks = color()?,
+                "Ke" => mtl.ke = color()?,
+                "Ns" => mtl.ns = scalar()?,
+                "d" => mtl.d = scalar()?,
+                "Tr" => mtl.d = 1.0 - scalar()?,
+                "Ni" => mtl.ni = scalar()?,
+                "illum" => mtl.illum = words.next().ok_or("材质数据解释失败")?.parse()?,
+                "map_Kd" => mtl.map_kd = words.last().map(String::from),
+                _ => {}
+            }
+        }
+
+        Ok(mtls)
+    }
+
+    pub fn material(&self) -> Material {
+        Material {
+            ambient: self.ka,
+            diffuse: self.kd,
+            specular: self.ks,
+            shininess: self.ns,
+            opacity: self.d,
+        }
+    }
+}
@@ -4,13 +4,23 @@
 
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::Path;
 
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Magnitude;
+use crate::la::Normal;
+use crate::la::Scale;
 use crate::la::Vector2;
 use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
 
 #[derive(Debug)]
 pub struct Vertex {
@@ -22,14 +32,20 @@ pub struct Vertex {
 #[derive(Debug)]
 pub struct Triangle {
     pub vs: Vec<Vertex>,
+    /// Index into `Obj::materials` of the last `usemtl` before the face.
+    pub material: Option<usize>,
 }
 
 #[derive(Debug)]
 pub struct Obj {
     pub vs: Vec<Vector3<f32>>,
-    vts: Vec<Vector2<f32>>,
-    vns: Vec<Vector3<f32>>,
+    pub vts: Vec<Vector2<f32>>,
+    pub vns: Vec<Vector3<f32>>,
     pub fs: Vec<Triangle>,
+    /// Files named by `mtllib`, relative to the OBJ file.
+    pub mtllibs: Vec<String>,
+    /// Names used by `usemtl`.
+    pub materials: Vec<String>,
 }
 
 impl Obj {
@@ -44,59 +60,203 @@ impl Obj {
         let mut vts = Vec::new();
         let mut vns = Vec::new();
         let mut fs = Vec::new();
+        let mut mtllibs = Vec::new();
+        let mut materials: Vec<String> = Vec::new();
+        let mut material = None;
 
         for line in content.lines() {
-            if line.starts_with("v ") {
-                let ds: Vec<f32> = line.split_whitespace().skip(1).take(3).map(|w| w.parse().unwrap()).collect();
-                let Ok(v0) = ds[0].try_into() else {
+            if line.starts_with("mtllib ") {
+                mtllibs.extend(line.split_whitespace().skip(1).map(String::from));
+            } else if let Some(name) = line.strip_prefix("usemtl ") {
+                let name = name.trim();
+                material = Some(match materials.iter().position(|m| m == name) {
+                    Some(index) => index,
+                    None => {
+                        materials.push(name.to_string());
+                        materials.len() - 1
+                    }
+                });
+            } else if line.starts_with("v ") {
+                let ds: Vec<f32> = line.split_whitespace().skip(1).take(3).map(|w| w.parse()).collect::<Result<_, _>>()?;
+                if 3 > ds.len() {
                     return Err("err".into());
-                };
-                let Ok(v1) = ds[1].try_into() else {
-                    return Err("err".into());
-                };
-                let Ok(v2) = ds[2].try_into() else {
-                    return Err("err".into());
-                };
-                vs.push([v0, v1, v2].into());
+                }
+                vs.push([ds[0], ds[1], ds[2]].into());
             } else if line.starts_with("vt ") {
-                let ds: Vec<f32> = line.split_whitespace().skip(1).take(2).map(|w| w.parse().unwrap()).collect();
-                let Ok(v0) = ds[0].try_into() else {
-                        return Err("err".into());
-                    };
-                let Ok(v1) = ds[1].try_into() else {
-                        return Err("err".into());
-                    };
-                vts.push([v0, v1].into());
+                let ds: Vec<f32> = line.split_whitespace().skip(1).take(2).map(|w| w.parse()).collect::<Result<_, _>>()?;
+                if 2 > ds.len() {
+                    return Err("err".into());
+                }
+                vts.push([ds[0], ds[1]].into());
             } else if line.starts_with("vn ") {
-                let ds: Vec<f32> = line.split_whitespace().skip(1).take(3).map(|w| w.parse().unwrap()).collect();
-                let Ok(v0) = ds[0].try_into() else {
-                        return Err("err".into());
-                    };
-                let Ok(v1) = ds[1].try_into() else {
-                        return Err("err".into());
-                    };
-                let Ok(v2) = ds[2].try_into() else {
-                        return Err("err".into());
-                    };
-                vns.push([v0, v1, v2].into());
+                let ds: Vec<f32> = line.split_whitespace().skip(1).take(3).map(|w| w.parse()).collect::<Result<_, _>>()?;
+                if 3 > ds.len() {
+                    return Err("err".into());
+                }
+                vns.push([ds[0], ds[1], ds[2]].into());
             } else if line.starts_with("f ") {
-                let vs: Vec<Vertex> = line
+                // empty components are absent, negative ones count back from the last element read so far
+                let resolve = |w: Option<&str>, len: usize| -> Result<usize, Box<dyn Error>> {
+                    let w = w.unwrap_or("");
+                    if w.is_empty() {
+                        return Ok(0);
+                    }
+
+                    let index: isize = w.parse()?;
+                    let index = if 0 > index { len as isize + 1 + index } else { index };
+                    if 0 >= index {
+                        return Err("面索引解释失败".into());
+                    }
+
+                    Ok(index as usize)
+                };
+
+                let vertices = line
                     .split_whitespace()
                     .skip(1)
                     .take(3)
                     .map(|w| {
-                        let ds: Vec<usize> = w.split("/").map(|w| w.parse().unwrap()).collect();
-                        Vertex {
-                            vi: ds[0],
-                            vti: ds[1],
-                            vni: ds[2],
-                        }
+                        let mut ds = w.split('/');
+                        Ok(Vertex {
+                            vi: resolve(ds.next(), vs.len())?,
+                            vti: resolve(ds.next(), vts.len())?,
+                            vni: resolve(ds.next(), vns.len())?,
+                        })
                     })
-                    .collect();
-                fs.push(Triangle { vs });
+                    .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+                if 3 > vertices.len() || vertices.iter().any(|v| 0 == v.vi) {
+                    return Err("面索引解释失败".into());
+                }
+                fs.push(Triangle { vs: vertices, material });
+            }
+        }
+
+        if fs.iter().flat_map(|f| &f.vs).any(|v| v.vi > vs.len() || v.vti > vts.len() || v.vni > vns.len()) {
+            return Err("面索引越界".into());
+        }
+
+        Ok(Self { vs, vts, vns, fs, mtllibs, materials })
+    }
+
+    pub fn vert(&self, face: usize, nthvert: usize) -> Vector3<f32> {
+        self.vs[self.fs[face].vs[nthvert].vi - 1]
+    }
+
+    /// Falls back to `[0, 0]` when the face has no texture coordinates.
+    pub fn uv(&self, face: usize, nthvert: usize) -> Vector2<f32> {
+        match self.fs[face].vs[nthvert].vti {
+            0 => [0.0, 0.0].into(),
+            vti => self.vts[vti - 1],
+        }
+    }
+
+    /// Falls back to the face normal when the face has no vertex normals.
+    pub fn normal(&self, face: usize, nthvert: usize) -> Vector3<f32> {
+        match self.fs[face].vs[nthvert].vni {
+            0 => self.face_normal(face),
+            vni => self.vns[vni - 1].normal(),
+        }
+    }
+
+    /// Per corner tangents with the bitangent sign in `w`, `bitangent = w * normal × tangent` as in MikkTSpace.
+    pub fn tangents(&self) -> Vec<[Vector4<f32>; 3]> {
+        let mut accumulated: HashMap<(usize, usize, usize), [Vector3<f32>; 2]> = HashMap::new();
+
+        for face in 0..self.fs.len() {
+            let ps = [self.vert(face, 0), self.vert(face, 1), self.vert(face, 2)];
+            let uvs = [self.uv(face, 0), self.uv(face, 1), self.uv(face, 2)];
+
+            let e1 = ps[1] - ps[0];
+            let e2 = ps[2] - ps[0];
+            let d1 = uvs[1] - uvs[0];
+            let d2 = uvs[2] - uvs[0];
+
+            let det = d1.x() * d2.y() - d2.x() * d1.y();
+            if f32::EPSILON > det.abs() {
+                continue;
+            }
+
+            let r = 1.0 / det;
+            let tangent = (e1.scale(d2.y()) - e2.scale(d1.y())).scale(r);
+            let bitangent = (e2.scale(d1.x()) - e1.scale(d2.x())).scale(r);
+
+            for nthvert in 0..3 {
+                let a = ps[(nthvert + 1) % 3] - ps[nthvert];
+                let b = ps[(nthvert + 2) % 3] - ps[nthvert];
+                let angle = (a.dot(b) / (a.magnitude() * b.magnitude())).clamp(-1.0, 1.0).acos();
+
+                if !angle.is_finite() {
+                    continue;
+                }
+
+                let v = &self.fs[face].vs[nthvert];
+                let entry = accumulated.entry((v.vi, v.vti, v.vni)).or_insert([0.0.into(); 2]);
+                entry[0] += tangent.scale(angle);
+                entry[1] += bitangent.scale(angle);
+            }
+        }
+
+        (0..self.fs.len())
+            .map(|face| {
+                [0, 1, 2].map(|nthvert| {
+                    let v = &self.fs[face].vs[nthvert];
+                    let n = self.normal(face, nthvert);
+                    let [t, b] = accumulated.get(&(v.vi, v.vti, v.vni)).copied().unwrap_or([0.0.into(); 2]);
+
+                    let mut t = t - n.scale(n.dot(t));
+                    if 1e-12 > t.dot(t) {
+                        let axis: Vector3<f32> = if 0.9 > n.x().abs() { [1.0, 0.0, 0.0].into() } else { [0.0, 1.0, 0.0].into() };
+                        t = axis - n.scale(n.dot(axis));
+                    }
+                    let t = t.normal();
+
+                    let w = if 0.0 > n.cross(t).dot(b) { -1.0 } else { 1.0 };
+
+                    [t.x(), t.y(), t.z(), w].into()
+                })
+            })
+            .collect()
+    }
+
+    /// Neighbouring face across each edge `nthvert -> nthvert + 1`, vertices are matched by position so seams split only by texture coordinates or normals stay connected. Degenerate faces are left out.
+    pub fn adjacency(&self) -> Vec<[Option<usize>; 3]> {
+        let mut positions: HashMap<[u32; 3], usize> = HashMap::new();
+        let welded: Vec<usize> = self
+            .vs
+            .iter()
+            .map(|v| {
+                let next = positions.len();
+                *positions.entry([v.x() + 0.0, v.y() + 0.0, v.z() + 0.0].map(f32::to_bits)).or_insert(next)
+            })
+            .collect();
+
+        let corner = |face: usize, nthvert: usize| welded[self.fs[face].vs[nthvert].vi - 1];
+
+        let degenerate = |face: usize| corner(face, 0) == corner(face, 1) || corner(face, 1) == corner(face, 2) || corner(face, 2) == corner(face, 0);
+
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for face in (0..self.fs.len()).filter(|&face| !degenerate(face)) {
+            for nthvert in 0..3 {
+                edges.insert((corner(face, nthvert), corner(face, (nthvert + 1) % 3)), face);
             }
         }
 
-        Ok(Self { vs, vts, vns, fs })
+        (0..self.fs.len())
+            .map(|face| {
+                if degenerate(face) {
+                    return [None; 3];
+                }
+                [0, 1, 2].map(|nthvert| edges.get(&(corner(face, (nthvert + 1) % 3), corner(face, nthvert))).copied())
+            })
+            .collect()
+    }
+
+    /// Counter-clockwise winding gives the outward normal.
+    pub fn face_normal(&self, face: usize) -> Vector3<f32> {
+        let v0 = self.vert(face, 0);
+        let v1 = self.vert(face, 1);
+        let v2 = self.vert(face, 2);
+
+        (v1 - v0).cross(v2 - v0).normal()
     }
 }
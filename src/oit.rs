@@ -0,0 +1,106 @@
+//! <https://jcgt.org/published/0002/02/09/>
+//!
+
+use crate::gl::is_front_facing;
+use crate::gl::rasterize;
+use crate::gl::CullMode;
+use crate::gl::RenderState;
+use crate::gl::Shader;
+use crate::img::Buffer;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Vector4;
+use crate::la::A;
+use crate::la::B;
+use crate::la::G;
+use crate::la::R;
+use crate::la::W;
+
+/// Weighted blended order independent transparency, accumulates translucent fragments in any order and composites them over an opaque image.
+#[derive(Clone, Debug)]
+pub struct WeightedOit {
+    /// Sum of weighted premultiplied colors, alpha in `w`.
+    pub accumulation: Buffer<Vector4<f32>>,
+    /// Product of `1 - alpha` of every fragment.
+    pub revealage: Buffer<f32>,
+}
+
+impl WeightedOit {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            accumulation: Buffer::new(width, height, 0.0.into()),
+            revealage: Buffer::new(width, height, 1.0),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.accumulation.fill(0.0.into());
+        self.revealage.fill(1.0);
+    }
+
+    /// Depth tests against the opaque `depth` without writing it, the blend state of `state` is ignored.
+    pub fn draw<S: Shader>(&mut self, faces: usize, shader: &mut S, depth: &DepthBuffer, state: &RenderState) {
+        let width = self.revealage.get_width();
+        let height = self.revealage.get_height();
+
+        for face in 0..faces {
+            let clip = [shader.vertex(face, 0), shader.vertex(face, 1), shader.vertex(face, 2)];
+
+            match state.cull {
+                CullMode::Back if !is_front_facing(&clip) => continue,
+                CullMode::Front if is_front_facing(&clip) => continue,
+                _ => {}
+            }
+
+            rasterize(&clip, width, height, |fragment| {
+                let coord = [fragment.x as u16, fragment.y as u16].into();
+
+                if !state.depth_test.test(fragment.depth, depth.get(coord)) {
+                    return;
+                }
+
+                let Some(color) = shader.fragment(fragment) else {
+                    return;
+                };
+
+                let alpha = color.a() as f32 / 255.0;
+                if 0.0 >= alpha {
+                    return;
+                }
+
+                let weight = alpha * (3e3 * (1.0 - fragment.depth).powi(3)).clamp(1e-2, 3e3);
+                let premultiplied = alpha * weight / 255.0;
+
+                let sum = self.accumulation.get(coord) + Vector4::from([color.r() as f32 * premultiplied, color.g() as f32 * premultiplied, color.b() as f32 * premultiplied, alpha * weight]);
+                self.accumulation.set(coord, sum);
+                self.revealage.set(coord, self.revealage.get(coord) * (1.0 - alpha));
+            });
+        }
+    }
+
+    /// Composites the accumulated layers over `image`.
+    pub fn resolve<I: Image>(&self, image: &mut I) {
+        let width = self.revealage.get_width().min(image.get_width());
+        let height = self.revealage.get_height().min(image.get_height());
+
+        for y in 0..height {
+            for x in 0..width {
+                let coord = [x, y].into();
+
+                let revealage = self.revealage.get(coord);
+                if 1.0 <= revealage {
+                    continue;
+                }
+
+                let sum = self.accumulation.get(coord);
+                let total = sum.w().max(1e-5);
+                let dst = image.get_color(coord);
+
+                let mix = |c: f32, d: u8| ((c / total * 255.0).clamp(0.0, 255.0) * (1.0 - revealage) + d as f32 * revealage + 0.5) as u8;
+                let alpha = 1.0 - (1.0 - dst.a() as f32 / 255.0) * revealage;
+
+                image.set_color(coord, [mix(sum[0][0], dst.r()), mix(sum[0][1], dst.g()), mix(sum[0][2], dst.b()), (alpha * 255.0 + 0.5) as u8].into());
+            }
+        }
+    }
+}
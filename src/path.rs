@@ -0,0 +1,418 @@
+//! <https://pbr-book.org/3ed-2018/Light_Transport_I_Surface_Reflection/Path_Tracing>
+//! <https://graphics.stanford.edu/courses/cs348b-03/papers/veach-chapter9.pdf>
+//!
+
+use std::error::Error;
+use std::f32::consts::PI;
+use std::path::Path;
+
+use crate::hdr;
+use crate::img::Buffer;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Magnitude;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::pbr::basis;
+use crate::ray::Finish;
+use crate::ray::PrimaryRays;
+use crate::ray::Ray;
+use crate::ray::Scene;
+use crate::rng::Rng;
+use crate::shader::reflect;
+use crate::shader::refract;
+use crate::shader::to_color;
+use crate::shader::Camera;
+use crate::shader::Material;
+use crate::tga::TGAImage;
+
+/// Cosine weighted direction around `n`, the density is `cos / π`.
+pub fn sample_cosine(n: Vector3<f32>, u1: f32, u2: f32) -> Vector3<f32> {
+    let (t, b) = basis(n);
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+
+    (t.scale(r * phi.cos()) + b.scale(r * phi.sin()) + n.scale((1.0 - u1).max(0.0).sqrt())).normal()
+}
+
+/// Energy conserving modified Phong, a Lambert lobe plus a `cos^shininess` lobe around the mirror direction.
+#[derive(Clone, Copy, Debug)]
+pub struct PhongBrdf {
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    pub shininess: f32,
+    /// Probability of sampling the Lambert lobe.
+    pub diffuse_probability: f32,
+}
+
+impl PhongBrdf {
+    /// Scales `diffuse + specular` down to at most 1 per channel.
+    pub fn new(material: &Material) -> Self {
+        let total = material.diffuse + material.specular;
+        let scale = 1.0 / total.x().max(total.y()).max(total.z()).max(1.0);
+
+        let diffuse = material.diffuse.scale(scale);
+        let specular = material.specular.scale(scale);
+
+        let weight = |c: Vector3<f32>| c.x() + c.y() + c.z();
+        let sum = weight(diffuse) + weight(specular);
+
+        Self {
+            diffuse,
+            specular,
+            shininess: material.shininess.max(0.0),
+            diffuse_probability: if 0.0 < sum { weight(diffuse) / sum } else { 1.0 },
+        }
+    }
+
+    pub fn eval(&self, n: Vector3<f32>, wo: Vector3<f32>, wi: Vector3<f32>) -> Vector3<f32> {
+        if 0.0 >= n.dot(wi) || 0.0 >= n.dot(wo) {
+            return 0.0.into();
+        }
+
+        let cos = reflect(wo.scale(-1.0), n).dot(wi).max(0.0);
+
+        self.diffuse.scale(1.0 / PI) + self.specular.scale((self.shininess + 2.0) / (2.0 * PI) * cos.powf(self.shininess))
+    }
+
+    pub fn pdf(&self, n: Vector3<f32>, wo: Vector3<f32>, wi: Vector3<f32>) -> f32 {
+        let cos_i = n.dot(wi);
+        if 0.0 >= cos_i {
+            return 0.0;
+        }
+
+        let cos = reflect(wo.scale(-1.0), n).dot(wi).max(0.0);
+
+        self.diffuse_probability * cos_i / PI + (1.0 - self.diffuse_probability) * (self.shininess + 1.0) / (2.0 * PI) * cos.powf(self.shininess)
+    }
+
+    /// Picks a lobe then importance samples it, `None` below the surface.
+    pub fn sample(&self, n: Vector3<f32>, wo: Vector3<f32>, rng: &mut Rng) -> Option<Vector3<f32>> {
+        let (u0, u1, u2) = (rng.next_f32(), rng.next_f32(), rng.next_f32());
+
+        let wi = if u0 < self.diffuse_probability {
+            sample_cosine(n, u1, u2)
+        } else {
+            let r = reflect(wo.scale(-1.0), n);
+            let (t, b) = basis(r);
+            let cos = u1.powf(1.0 / (self.shininess + 1.0));
+            let sin = (1.0 - cos * cos).max(0.0).sqrt();
+            let phi = 2.0 * PI * u2;
+            (t.scale(sin * phi.cos()) + b.scale(sin * phi.sin()) + r.scale(cos)).normal()
+        };
+
+        (0.0 < n.dot(wi)).then_some(wi)
+    }
+}
+
+/// Emissive triangles of a scene chosen in proportion to their area.
+struct Emitters {
+    triangles: Vec<usize>,
+    cdf: Vec<f32>,
+    area: f32,
+}
+
+impl Emitters {
+    fn new(scene: &Scene) -> Self {
+        let mut triangles = Vec::new();
+        let mut cdf = Vec::new();
+        let mut area = 0.0;
+
+        for (triangle, [v0, v1, v2]) in scene.triangles.iter().enumerate() {
+            let emission = scene.finishes[scene.owners[triangle]].emission;
+            if 0.0 >= emission.x().max(emission.y()).max(emission.z()) {
+                continue;
+            }
+
+            let a = (*v1 - *v0).cross(*v2 - *v0).magnitude() * 0.5;
+            if 0.0 >= a {
+                continue;
+            }
+
+            area += a;
+            triangles.push(triangle);
+            cdf.push(area);
+        }
+
+        Self { triangles, cdf, area }
+    }
+
+    /// Point, unit normal and emitting triangle.
+    fn sample(&self, scene: &Scene, rng: &mut Rng) -> Option<(Vector3<f32>, Vector3<f32>, usize)> {
+        if self.triangles.is_empty() {
+            return None;
+        }
+
+        let target = rng.next_f32() * self.area;
+        let index = self.cdf.partition_point(|&c| c <= target).min(self.triangles.len() - 1);
+        let triangle = self.triangles[index];
+
+        let [v0, v1, v2] = scene.triangles[triangle];
+        let (u1, u2) = (rng.next_f32(), rng.next_f32());
+        let r = u1.sqrt();
+        let p = v0.scale(1.0 - r) + v1.scale(r * (1.0 - u2)) + v2.scale(r * u2);
+
+        Some((p, (v1 - v0).cross(v2 - v0).normal(), triangle))
+    }
+
+    /// Solid angle density of reaching a point at `distance` whose normal makes `cos` with the direction back to the shaded point.
+    fn pdf(&self, distance: f32, cos: f32) -> f32 {
+        if 0.0 >= cos || 0.0 >= self.area {
+            return 0.0;
+        }
+
+        distance * distance / (cos * self.area)
+    }
+}
+
+fn power_heuristic(a: f32, b: f32) -> f32 {
+    let (a, b) = (a * a, b * b);
+
+    if 0.0 < a + b {
+        a / (a + b)
+    } else {
+        0.0
+    }
+}
+
+/// Accumulated radiance of every pixel and the number of samples taken.
+#[derive(Clone, Debug)]
+pub struct Film {
+    pub sum: Buffer<Vector3<f32>>,
+    pub samples: u32,
+    /// Non-finite samples left out of `sum` per pixel.
+    pub dropped: Buffer<u32>,
+}
+
+impl Film {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            sum: Buffer::new(width, height, 0.0.into()),
+            samples: 0,
+            dropped: Buffer::new(width, height, 0),
+        }
+    }
+
+    /// Mean radiance per pixel over the samples that were kept.
+    pub fn radiance(&self) -> Buffer<Vector3<f32>> {
+        let mut radiance = self.sum.clone();
+
+        for (c, &dropped) in radiance.as_mut_slice().iter_mut().zip(self.dropped.as_slice()) {
+            *c = c.scale(1.0 / self.samples.saturating_sub(dropped).max(1) as f32);
+        }
+
+        radiance
+    }
+
+    /// Clamped like the rasterizer output.
+    pub fn image(&self) -> TGAImage {
+        let radiance = self.radiance();
+        let mut image = TGAImage::new(radiance.get_width(), radiance.get_height());
+
+        for y in 0..radiance.get_height() {
+            for x in 0..radiance.get_width() {
+                image.set_color([x, y].into(), to_color(radiance.get([x, y].into())));
+            }
+        }
+
+        image
+    }
+
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        hdr::save(&self.radiance(), path)
+    }
+}
+
+/// Unidirectional path tracer over the finishes of a `Scene`, Phong surfaces get next event estimation towards the scene lights and emissive triangles, the ambient term is replaced by indirect light.
+#[derive(Clone, Copy, Debug)]
+pub struct PathTracer {
+    pub max_depth: u32,
+    /// Bounces before Russian roulette starts.
+    pub russian_roulette_depth: u32,
+    pub seed: u64,
+    pub background: Vector3<f32>,
+    /// Offset of secondary ray origins along the geometric normal.
+    pub bias: f32,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self {
+            max_depth: 16,
+            russian_roulette_depth: 3,
+            seed: 0,
+            background: 0.0.into(),
+            bias: 1e-4,
+        }
+    }
+}
+
+impl PathTracer {
+    /// Adds `samples` jittered paths per pixel, every sample has its own random stream so the result does not depend on how the samples are split between calls.
+    pub fn render(&self, scene: &Scene, camera: &Camera, film: &mut Film, samples: u32) -> Result<(), Box<dyn Error>> {
+        let emitters = Emitters::new(scene);
+
+        let width = film.sum.get_width();
+        let height = film.sum.get_height();
+        let primary = PrimaryRays::new(camera, width, height).ok_or("相机矩阵不可逆")?;
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = film.sum.get([x, y].into());
+                let mut dropped = film.dropped.get([x, y].into());
+
+                for sample in film.samples..film.samples + samples {
+                    let mut rng = Rng::new(self.seed ^ ((y as u64 * width as u64 + x as u64) << 32 | sample as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+                    let ray = primary.ray(x as f32 + rng.next_f32(), y as f32 + rng.next_f32());
+                    let radiance = self.radiance(scene, &emitters, ray, &mut rng);
+
+                    if radiance.x().is_finite() && radiance.y().is_finite() && radiance.z().is_finite() {
+                        sum += radiance;
+                    } else {
+                        dropped += 1;
+                    }
+                }
+
+                film.sum.set([x, y].into(), sum);
+                film.dropped.set([x, y].into(), dropped);
+            }
+        }
+
+        film.samples += samples;
+
+        Ok(())
+    }
+
+    /// Renders up to each count of `checkpoints` in increasing order and hands the film to `f` after each.
+    pub fn render_progressive<F: FnMut(&Film) -> Result<(), Box<dyn Error>>>(&self, scene: &Scene, camera: &Camera, film: &mut Film, checkpoints: &[u32], mut f: F) -> Result<(), Box<dyn Error>> {
+        for &checkpoint in checkpoints {
+            if checkpoint > film.samples {
+                self.render(scene, camera, film, checkpoint - film.samples)?;
+            }
+
+            f(film)?;
+        }
+
+        Ok(())
+    }
+
+    fn radiance(&self, scene: &Scene, emitters: &Emitters, mut ray: Ray, rng: &mut Rng) -> Vector3<f32> {
+        let mut color = Vector3::<f32>::from(0.0);
+        let mut throughput = Vector3::<f32>::from(1.0);
+        let mut previous_pdf: Option<f32> = None;
+
+        for depth in 0..self.max_depth {
+            let Some(hit) = scene.intersect(&ray, f32::INFINITY) else {
+                color += throughput.hadamard(self.background);
+                break;
+            };
+
+            let finish: &Finish = scene.finish(&hit);
+            let p = ray.at(hit.t);
+
+            let [v0, v1, v2] = scene.triangles[hit.triangle];
+            let geometric = (v1 - v0).cross(v2 - v0).normal();
+            let entering = 0.0 > geometric.dot(ray.direction);
+            let outward = if entering { geometric } else { geometric.scale(-1.0) };
+
+            let mut n = scene.normal(&hit);
+            if 0.0 < n.dot(ray.direction) {
+                n = n.scale(-1.0);
+            }
+
+            let wo = ray.direction.scale(-1.0);
+
+            if entering {
+                let weight = match previous_pdf {
+                    Some(pdf) => power_heuristic(pdf, emitters.pdf(hit.t, geometric.dot(wo))),
+                    None => 1.0,
+                };
+                color += throughput.hadamard(finish.emission).scale(weight);
+            }
+
+            let u = rng.next_f32();
+
+            if u < finish.transparency {
+                let eta = if entering { 1.0 / finish.ior } else { finish.ior };
+                let r0 = ((1.0 - finish.ior) / (1.0 + finish.ior)).powi(2);
+
+                ray = match refract(ray.direction, n, eta) {
+                    Some(direction) => {
+                        let cos = if entering { wo.dot(n) } else { direction.dot(n.scale(-1.0)) };
+                        let fresnel = r0 + (1.0 - r0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5);
+
+                        if rng.next_f32() < fresnel {
+                            Ray::new(p + outward.scale(self.bias), reflect(ray.direction, n))
+                        } else {
+                            Ray::new(p - outward.scale(self.bias), direction)
+                        }
+                    }
+                    None => Ray::new(p + outward.scale(self.bias), reflect(ray.direction, n)),
+                };
+                previous_pdf = None;
+            } else if u < finish.transparency + (1.0 - finish.transparency) * finish.reflectivity {
+                ray = Ray::new(p + outward.scale(self.bias), reflect(ray.direction, n));
+                previous_pdf = None;
+            } else {
+                let brdf = PhongBrdf::new(&finish.material);
+                let origin = p + outward.scale(self.bias);
+
+                for light in &scene.lights {
+                    light.illuminate(p, |sample| {
+                        let cos = n.dot(sample.direction);
+                        if 0.0 >= cos || scene.occluded(&Ray::new(origin, sample.direction), sample.distance) {
+                            return;
+                        }
+
+                        color += throughput.hadamard(sample.radiance).hadamard(brdf.eval(n, wo, sample.direction)).scale(cos);
+                    });
+                }
+
+                if let Some((q, normal, triangle)) = emitters.sample(scene, rng) {
+                    let d = q - origin;
+                    let distance = d.magnitude();
+                    let wi = d.scale(1.0 / distance.max(f32::MIN_POSITIVE));
+
+                    let cos = n.dot(wi);
+                    let light_pdf = emitters.pdf(distance, -normal.dot(wi));
+
+                    if 0.0 < distance && 0.0 < cos && 0.0 < light_pdf && !scene.occluded(&Ray::new(origin, wi), distance * (1.0 - 1e-3)) {
+                        let emission = scene.finishes[scene.owners[triangle]].emission;
+                        let weight = power_heuristic(light_pdf, brdf.pdf(n, wo, wi));
+
+                        color += throughput.hadamard(emission).hadamard(brdf.eval(n, wo, wi)).scale(cos * weight / light_pdf);
+                    }
+                }
+
+                let Some(wi) = brdf.sample(n, wo, rng) else {
+                    break;
+                };
+
+                let pdf = brdf.pdf(n, wo, wi);
+                if 0.0 >= pdf {
+                    break;
+                }
+
+                throughput = throughput.hadamard(brdf.eval(n, wo, wi)).scale(n.dot(wi) / pdf);
+                ray = Ray::new(origin, wi);
+                previous_pdf = Some(pdf);
+            }
+
+            if depth + 1 >= self.russian_roulette_depth {
+                let survival = throughput.x().max(throughput.y()).max(throughput.z()).min(0.95);
+                if rng.next_f32() >= survival {
+                    break;
+                }
+                throughput = throughput.scale(1.0 / survival);
+            }
+        }
+
+        color
+    }
+}
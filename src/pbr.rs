@@ -0,0 +1,540 @@
+//! <https://learnopengl.com/PBR/Theory>
+//! <https://learnopengl.com/PBR/IBL/Specular-IBL>
+//! <https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#appendix-b-brdf-implementation>
+//!
+
+use std::f32::consts::PI;
+
+use crate::gl::embed;
+use crate::gl::Fragment;
+use crate::gl::Shader;
+use crate::img::sample;
+use crate::img::to_float;
+use crate::img::Buffer;
+use crate::img::Image;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::obj::Obj;
+use crate::shader::interpolate;
+use crate::shader::reflect;
+use crate::shader::tbn;
+use crate::shader::to_color;
+use crate::shader::transform_normal;
+use crate::shader::transform_point;
+use crate::shader::Uniforms;
+use crate::tga::TGAColor;
+use crate::tga::TGAImage;
+
+/// glTF metallic-roughness inputs, every map is multiplied with its factor.
+#[derive(Clone, Copy)]
+pub struct PbrMaterial<'a> {
+    pub base_color: Vector3<f32>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: Vector3<f32>,
+    pub occlusion_strength: f32,
+    pub base_color_map: Option<&'a TGAImage>,
+    /// Roughness in green, metallic in blue.
+    pub metallic_roughness_map: Option<&'a TGAImage>,
+    /// Occlusion in red.
+    pub occlusion_map: Option<&'a TGAImage>,
+    pub emissive_map: Option<&'a TGAImage>,
+    /// Tangent space normals.
+    pub normal_map: Option<&'a TGAImage>,
+}
+
+impl Default for PbrMaterial<'_> {
+    fn default() -> Self {
+        Self {
+            base_color: [1.0, 1.0, 1.0].into(),
+            metallic: 0.0,
+            roughness: 0.5,
+            emissive: 0.0.into(),
+            occlusion_strength: 1.0,
+            base_color_map: None,
+            metallic_roughness_map: None,
+            occlusion_map: None,
+            emissive_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+/// Inputs of the BRDF at one surface point.
+#[derive(Clone, Copy, Debug)]
+pub struct Surface {
+    pub albedo: Vector3<f32>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub occlusion: f32,
+    pub emissive: Vector3<f32>,
+}
+
+impl PbrMaterial<'_> {
+    pub fn surface(&self, uv: Vector2<f32>) -> Surface {
+        let mut surface = Surface {
+            albedo: self.base_color,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            occlusion: 1.0,
+            emissive: self.emissive,
+        };
+
+        if let Some(map) = self.base_color_map {
+            surface.albedo = surface.albedo.hadamard(Vector3::from(sample(map, uv)));
+        }
+
+        if let Some(map) = self.metallic_roughness_map {
+            let texel = sample(map, uv);
+            surface.roughness *= texel.y();
+            surface.metallic *= texel.z();
+        }
+
+        if let Some(map) = self.occlusion_map {
+            surface.occlusion = 1.0 + self.occlusion_strength * (sample(map, uv).x() - 1.0);
+        }
+
+        if let Some(map) = self.emissive_map {
+            surface.emissive = surface.emissive.hadamard(Vector3::from(sample(map, uv)));
+        }
+
+        surface.metallic = surface.metallic.clamp(0.0, 1.0);
+        surface.roughness = surface.roughness.clamp(0.04, 1.0);
+
+        surface
+    }
+}
+
+/// Trowbridge-Reitz normal distribution with `alpha = roughness^2`.
+pub fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a2 = roughness.powi(4);
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    a2 / (PI * d * d)
+}
+
+/// Smith masking-shadowing with the Schlick-GGX approximation, `k` is `(r + 1)^2 / 8` for analytic lights and `r^2 / 2` for image based lighting.
+pub fn geometry_smith(n_dot_v: f32, n_dot_l: f32, k: f32) -> f32 {
+    let g1 = |x: f32| x / (x * (1.0 - k) + k);
+
+    g1(n_dot_v) * g1(n_dot_l)
+}
+
+pub fn fresnel_schlick(cos: f32, f0: Vector3<f32>) -> Vector3<f32> {
+    f0 + (Vector3::from(1.0) - f0).scale((1.0 - cos).clamp(0.0, 1.0).powi(5))
+}
+
+/// Schlick Fresnel with the grazing reflectance capped by roughness, used for ambient lighting.
+pub fn fresnel_schlick_roughness(cos: f32, f0: Vector3<f32>, roughness: f32) -> Vector3<f32> {
+    let grazing = Vector3::from([f0.x().max(1.0 - roughness), f0.y().max(1.0 - roughness), f0.z().max(1.0 - roughness)]);
+
+    f0 + (grazing - f0).scale((1.0 - cos).clamp(0.0, 1.0).powi(5))
+}
+
+/// Outgoing radiance factor for unit vectors `n`, `l` (towards the light) and `v` (towards the eye), including the cosine term.
+pub fn cook_torrance(surface: &Surface, n: Vector3<f32>, l: Vector3<f32>, v: Vector3<f32>) -> Vector3<f32> {
+    let n_dot_l = n.dot(l);
+    let n_dot_v = n.dot(v).max(1e-4);
+
+    if 0.0 >= n_dot_l {
+        return 0.0.into();
+    }
+
+    let h = (l + v).normal();
+    let f0 = Vector3::<f32>::from(0.04).scale(1.0 - surface.metallic) + surface.albedo.scale(surface.metallic);
+
+    let f = fresnel_schlick(h.dot(v).max(0.0), f0);
+    let d = distribution_ggx(n.dot(h).max(0.0), surface.roughness);
+    let g = geometry_smith(n_dot_v, n_dot_l, (surface.roughness + 1.0).powi(2) / 8.0);
+
+    let specular = f.scale(d * g / (4.0 * n_dot_v * n_dot_l));
+    let diffuse = (Vector3::from(1.0) - f).scale(1.0 - surface.metallic).hadamard(surface.albedo).scale(1.0 / PI);
+
+    (diffuse + specular).scale(n_dot_l)
+}
+
+/// `i`-th of `count` points of the Hammersley set.
+pub fn hammersley(i: u32, count: u32) -> Vector2<f32> {
+    [i as f32 / count as f32, i.reverse_bits() as f32 / 4294967296.0].into()
+}
+
+/// Half vector around `n` distributed like the GGX lobe of `roughness`.
+pub fn importance_sample_ggx(xi: Vector2<f32>, n: Vector3<f32>, roughness: f32) -> Vector3<f32> {
+    let a = roughness * roughness;
+
+    let phi = 2.0 * PI * xi.x();
+    let cos_theta = ((1.0 - xi.y()) / (1.0 + (a * a - 1.0) * xi.y())).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+    let (t, b) = basis(n);
+
+    (t.scale(phi.cos() * sin_theta) + b.scale(phi.sin() * sin_theta) + n.scale(cos_theta)).normal()
+}
+
+/// Two unit vectors completing `n` to an orthonormal basis.
+pub fn basis(n: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let up: Vector3<f32> = if 0.999 > n.z().abs() { [0.0, 0.0, 1.0].into() } else { [1.0, 0.0, 0.0].into() };
+    let t = up.cross(n).normal();
+
+    (t, n.cross(t))
+}
+
+/// Split-sum scale and bias applied to `F0`, indexed by `n·v` along x and roughness along y.
+pub fn brdf_lut(size: u16, samples: u32) -> Buffer<Vector2<f32>> {
+    let mut lut = Buffer::new(size, size, Vector2::from(0.0));
+    let n: Vector3<f32> = [0.0, 0.0, 1.0].into();
+
+    for y in 0..size {
+        let roughness = (y as f32 + 0.5) / size as f32;
+
+        for x in 0..size {
+            let n_dot_v = (x as f32 + 0.5) / size as f32;
+            let v: Vector3<f32> = [(1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v].into();
+
+            let (mut a, mut b) = (0.0, 0.0);
+
+            for i in 0..samples {
+                let h = importance_sample_ggx(hammersley(i, samples), n, roughness);
+                let l = reflect(v.scale(-1.0), h);
+
+                let n_dot_l = l.z();
+                if 0.0 >= n_dot_l {
+                    continue;
+                }
+
+                let n_dot_h = h.z().max(0.0);
+                let v_dot_h = v.dot(h).max(0.0);
+
+                let g = geometry_smith(n_dot_v, n_dot_l, roughness * roughness / 2.0);
+                let g_vis = g * v_dot_h / (n_dot_h * n_dot_v);
+                let fc = (1.0 - v_dot_h).powi(5);
+
+                a += (1.0 - fc) * g_vis;
+                b += fc * g_vis;
+            }
+
+            lut.set([x, y].into(), [a / samples as f32, b / samples as f32].into());
+        }
+    }
+
+    lut
+}
+
+/// Texture coordinate of `direction` in an equirectangular panorama, `-z` maps to the center and `+y` to the top row.
+pub fn equirect_uv(direction: Vector3<f32>) -> Vector2<f32> {
+    let d = direction.normal();
+
+    [0.5 + d.x().atan2(-d.z()) / (2.0 * PI), 0.5 + d.y().clamp(-1.0, 1.0).asin() / PI].into()
+}
+
+pub fn equirect_direction(uv: Vector2<f32>) -> Vector3<f32> {
+    let phi = (uv.x() - 0.5) * 2.0 * PI;
+    let theta = (uv.y() - 0.5) * PI;
+
+    [phi.sin() * theta.cos(), theta.sin(), -phi.cos() * theta.cos()].into()
+}
+
+/// Bilinear lookup of an equirectangular radiance buffer, wrapping horizontally and clamping vertically.
+pub fn sample_equirect(map: &Buffer<Vector3<f32>>, direction: Vector3<f32>) -> Vector3<f32> {
+    let width = map.get_width() as i32;
+    let height = map.get_height() as i32;
+
+    let uv = equirect_uv(direction);
+    let x = uv.x() * width as f32 - 0.5;
+    let y = uv.y() * height as f32 - 0.5;
+
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+
+    let texel = |x: i32, y: i32| map.get([x.rem_euclid(width) as u16, y.clamp(0, height - 1) as u16].into());
+
+    let (x0, y0) = (x0 as i32, y0 as i32);
+
+    let bottom = texel(x0, y0).scale(1.0 - tx) + texel(x0 + 1, y0).scale(tx);
+    let top = texel(x0, y0 + 1).scale(1.0 - tx) + texel(x0 + 1, y0 + 1).scale(tx);
+
+    bottom.scale(1.0 - ty) + top.scale(ty)
+}
+
+/// Prefiltered image based lighting built from an equirectangular environment.
+#[derive(Clone, Debug)]
+pub struct Environment {
+    /// Cosine convolved radiance divided by pi, multiply by albedo for the diffuse term.
+    pub irradiance: Buffer<Vector3<f32>>,
+    /// GGX prefiltered radiance, level `i` of `n` has roughness `i / (n - 1)` and half the size of the previous one.
+    pub specular: Vec<Buffer<Vector3<f32>>>,
+    pub brdf: Buffer<Vector2<f32>>,
+}
+
+impl Environment {
+    pub fn from_radiance(radiance: Buffer<Vector3<f32>>, levels: usize, samples: u32) -> Self {
+        let levels = levels.max(2);
+
+        let mut mips = vec![radiance];
+        while mips.len() < levels.max(6) {
+            let next = downsample(mips.last().unwrap());
+            mips.push(next);
+        }
+
+        let specular = (0..levels)
+            .map(|level| {
+                if 0 == level {
+                    return mips[0].clone();
+                }
+
+                let roughness = level as f32 / (levels - 1) as f32;
+                let source = &mips[level.min(mips.len() - 1)];
+                prefilter(source, roughness, samples)
+            })
+            .collect();
+
+        let small = mips.iter().rev().find(|mip| 32 <= mip.get_width()).unwrap_or(&mips[0]);
+
+        Self {
+            irradiance: convolve(small),
+            specular,
+            brdf: brdf_lut(32, samples.max(64)),
+        }
+    }
+
+    /// Builds the environment from an equirectangular image with channels scaled to `[0, 1]`.
+    pub fn from_equirect<I: Image>(image: &I, levels: usize, samples: u32) -> Self {
+        let mut radiance = Buffer::new(image.get_width(), image.get_height(), Vector3::from(0.0));
+
+        for y in 0..image.get_height() {
+            for x in 0..image.get_width() {
+                radiance.set([x, y].into(), Vector3::from(to_float(image.get_color([x, y].into()))));
+            }
+        }
+
+        Self::from_radiance(radiance, levels, samples)
+    }
+
+    pub fn diffuse(&self, n: Vector3<f32>) -> Vector3<f32> {
+        sample_equirect(&self.irradiance, n)
+    }
+
+    /// Prefiltered radiance along `r`, interpolated between the two closest roughness levels.
+    pub fn specular(&self, r: Vector3<f32>, roughness: f32) -> Vector3<f32> {
+        let level = roughness.clamp(0.0, 1.0) * (self.specular.len() - 1) as f32;
+        let (lo, hi) = (level.floor() as usize, level.ceil() as usize);
+        let t = level - lo as f32;
+
+        sample_equirect(&self.specular[lo], r).scale(1.0 - t) + sample_equirect(&self.specular[hi], r).scale(t)
+    }
+
+    pub fn brdf(&self, n_dot_v: f32, roughness: f32) -> Vector2<f32> {
+        let size = self.brdf.get_width() as f32;
+        let x = (n_dot_v * size).clamp(0.0, size - 1.0) as u16;
+        let y = (roughness * size).clamp(0.0, size - 1.0) as u16;
+
+        self.brdf.get([x, y].into())
+    }
+
+    /// Split-sum ambient lighting, diffuse from irradiance plus specular from the prefiltered levels.
+    pub fn ambient(&self, surface: &Surface, n: Vector3<f32>, v: Vector3<f32>) -> Vector3<f32> {
+        let n_dot_v = n.dot(v).max(1e-4);
+        let f0 = Vector3::<f32>::from(0.04).scale(1.0 - surface.metallic) + surface.albedo.scale(surface.metallic);
+
+        let f = fresnel_schlick_roughness(n_dot_v, f0, surface.roughness);
+        let kd = (Vector3::from(1.0) - f).scale(1.0 - surface.metallic);
+
+        let diffuse = kd.hadamard(surface.albedo).hadamard(self.diffuse(n));
+
+        let brdf = self.brdf(n_dot_v, surface.roughness);
+        let specular = self.specular(reflect(v.scale(-1.0), n), surface.roughness).hadamard(f.scale(brdf.x()) + Vector3::from(brdf.y()));
+
+        diffuse + specular
+    }
+}
+
+fn downsample(map: &Buffer<Vector3<f32>>) -> Buffer<Vector3<f32>> {
+    let width = (map.get_width() / 2).max(1);
+    let height = (map.get_height() / 2).max(1);
+
+    let mut result = Buffer::new(width, height, Vector3::from(0.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = Vector3::from(0.0);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let sx = (x * 2 + dx).min(map.get_width() - 1);
+                let sy = (y * 2 + dy).min(map.get_height() - 1);
+                sum += map.get([sx, sy].into());
+            }
+            result.set([x, y].into(), sum.scale(0.25));
+        }
+    }
+
+    result
+}
+
+fn prefilter(source: &Buffer<Vector3<f32>>, roughness: f32, samples: u32) -> Buffer<Vector3<f32>> {
+    let width = source.get_width();
+    let height = source.get_height();
+
+    let mut result = Buffer::new(width, height, Vector3::from(0.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let n = equirect_direction([(x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32].into());
+
+            let mut sum = Vector3::from(0.0);
+            let mut weight = 0.0;
+
+            for i in 0..samples {
+                let h = importance_sample_ggx(hammersley(i, samples), n, roughness);
+                let l = reflect(n.scale(-1.0), h);
+
+                let n_dot_l = n.dot(l);
+                if 0.0 < n_dot_l {
+                    sum += sample_equirect(source, l).scale(n_dot_l);
+                    weight += n_dot_l;
+                }
+            }
+
+            result.set([x, y].into(), sum.scale(1.0 / weight.max(1e-4)));
+        }
+    }
+
+    result
+}
+
+fn convolve(source: &Buffer<Vector3<f32>>) -> Buffer<Vector3<f32>> {
+    let width = source.get_width();
+    let height = source.get_height();
+
+    let texels: Vec<(Vector3<f32>, Vector3<f32>)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let v = (y as f32 + 0.5) / height as f32;
+            let direction = equirect_direction([(x as f32 + 0.5) / width as f32, v].into());
+            let solid_angle = (2.0 * PI / width as f32) * (PI / height as f32) * ((v - 0.5) * PI).cos();
+            (direction, source.get([x, y].into()).scale(solid_angle / PI))
+        })
+        .collect();
+
+    let mut result = Buffer::new(width, height, Vector3::from(0.0));
+
+    for y in 0..height {
+        for x in 0..width {
+            let n = equirect_direction([(x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32].into());
+
+            let mut sum = Vector3::from(0.0);
+            for &(direction, radiance) in &texels {
+                let cos = n.dot(direction);
+                if 0.0 < cos {
+                    sum += radiance.scale(cos);
+                }
+            }
+
+            result.set([x, y].into(), sum);
+        }
+    }
+
+    result
+}
+
+/// Cook-Torrance shading of the lights in `uniforms` plus image based or constant ambient lighting.
+pub struct PbrShader<'a> {
+    pub obj: &'a Obj,
+    pub uniforms: Uniforms,
+    pub material: PbrMaterial<'a>,
+    pub environment: Option<&'a Environment>,
+    /// Ambient radiance used without an environment.
+    pub ambient: Vector3<f32>,
+    tangents: Vec<[Vector4<f32>; 3]>,
+    varying_position: [Vector3<f32>; 3],
+    varying_normal: [Vector3<f32>; 3],
+    varying_tangent: [Vector4<f32>; 3],
+    varying_uv: [Vector2<f32>; 3],
+}
+
+impl<'a> PbrShader<'a> {
+    pub fn new(obj: &'a Obj, uniforms: Uniforms, material: PbrMaterial<'a>, environment: Option<&'a Environment>) -> Self {
+        Self {
+            obj,
+            uniforms,
+            material,
+            environment,
+            ambient: Vector3::from(0.03),
+            tangents: if material.normal_map.is_some() { obj.tangents() } else { Vec::new() },
+            varying_position: [0.0.into(); 3],
+            varying_normal: [0.0.into(); 3],
+            varying_tangent: [0.0.into(); 3],
+            varying_uv: [0.0.into(); 3],
+        }
+    }
+}
+
+impl Shader for PbrShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let position = transform_point(self.obj.vert(face, nthvert), &self.uniforms.model);
+
+        self.varying_position[nthvert] = position;
+        self.varying_normal[nthvert] = transform_normal(self.obj.normal(face, nthvert), &self.uniforms.normals);
+        self.varying_uv[nthvert] = self.obj.uv(face, nthvert);
+
+        if let Some(tangents) = self.tangents.get(face) {
+            let tangent = tangents[nthvert];
+            let t: Vector3<f32> = (embed(tangent.into(), 0.0) * self.uniforms.model).into();
+            self.varying_tangent[nthvert] = embed(t, tangent.w());
+        }
+
+        embed(position, 1.0) * self.uniforms.view_projection
+    }
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor> {
+        let bar = fragment.bar;
+
+        let position = interpolate(&self.varying_position, bar);
+        let uv = self.varying_uv[0].scale(bar.x()) + self.varying_uv[1].scale(bar.y()) + self.varying_uv[2].scale(bar.z());
+        let mut normal = interpolate(&self.varying_normal, bar).normal();
+
+        if let Some(normal_map) = self.material.normal_map {
+            let texel = sample(normal_map, uv);
+            let mapped: Vector3<f32> = [texel.x() * 2.0 - 1.0, texel.y() * 2.0 - 1.0, texel.z() * 2.0 - 1.0].into();
+            let tangent = self.varying_tangent[0].scale(bar.x()) + self.varying_tangent[1].scale(bar.y()) + self.varying_tangent[2].scale(bar.z());
+            let tangent = embed(tangent.into(), if 0.0 > tangent.w() { -1.0 } else { 1.0 });
+            normal = (mapped * tbn(tangent, normal)).normal();
+        }
+
+        if !fragment.front_facing {
+            normal = normal.scale(-1.0);
+        }
+
+        let surface = self.material.surface(uv);
+        let v = (self.uniforms.eye - position).normal();
+
+        let mut color = surface.emissive;
+
+        for (i, light) in self.uniforms.lights.iter().enumerate() {
+            let visibility = self.uniforms.visibility(i, position, normal);
+
+            if 0.0 >= visibility {
+                continue;
+            }
+
+            light.illuminate(position, |sample| color += sample.radiance.hadamard(cook_torrance(&surface, normal, sample.direction, v)).scale(visibility));
+        }
+
+        let ambient = match self.environment {
+            Some(environment) => environment.ambient(&surface, normal, v),
+            None => self.ambient.hadamard(surface.albedo),
+        };
+
+        color += ambient.scale(surface.occlusion * self.uniforms.ambient_occlusion(fragment));
+
+        Some(to_color(self.uniforms.fog(color, position)))
+    }
+}
@@ -0,0 +1,459 @@
+//! <https://developer.download.nvidia.com/assets/gamedev/files/sdk/11/FXAA_WhitePaper.pdf>
+//! <https://learnopengl.com/Advanced-Lighting/Bloom>
+//! <https://en.wikipedia.org/wiki/Unsharp_masking>
+//! <https://resolve.cafe/developers/luts/>
+//!
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::Path;
+
+use crate::img::to_float;
+use crate::img::Buffer;
+use crate::img::Image;
+use crate::la::Dot;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::shader::to_rgba;
+use crate::tga::TGAImage;
+
+const LUMA: [f32; 3] = [0.299, 0.587, 0.114];
+
+/// Image space effect reading `source` and writing every pixel of `target`, both have the same size.
+pub trait Pass {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image);
+}
+
+/// Passes run in order, each one reading the output of the previous.
+#[derive(Default)]
+pub struct PostStack<'a> {
+    pub passes: Vec<Box<dyn Pass + 'a>>,
+}
+
+impl<'a> PostStack<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<P: Pass + 'a>(mut self, pass: P) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn run(&self, image: &mut dyn Image) {
+        let mut source = TGAImage::new(image.get_width(), image.get_height());
+
+        for pass in &self.passes {
+            copy(image, &mut source);
+            pass.apply(&source, image);
+        }
+    }
+}
+
+/// Fast approximate anti-aliasing, blends along the local edge direction where the luma contrast is above the thresholds.
+#[derive(Clone, Copy, Debug)]
+pub struct Fxaa {
+    pub edge_threshold: f32,
+    pub edge_threshold_min: f32,
+    pub span_max: f32,
+    pub reduce_mul: f32,
+    pub reduce_min: f32,
+}
+
+impl Default for Fxaa {
+    fn default() -> Self {
+        Self {
+            edge_threshold: 0.125,
+            edge_threshold_min: 0.0312,
+            span_max: 8.0,
+            reduce_mul: 1.0 / 8.0,
+            reduce_min: 1.0 / 128.0,
+        }
+    }
+}
+
+impl Pass for Fxaa {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        for_each_pixel(source, target, |x, y, center| {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+            let nw = luma(sample_pixel(source, px - 1.0, py + 1.0));
+            let ne = luma(sample_pixel(source, px + 1.0, py + 1.0));
+            let sw = luma(sample_pixel(source, px - 1.0, py - 1.0));
+            let se = luma(sample_pixel(source, px + 1.0, py - 1.0));
+            let m = luma(center);
+
+            let min = m.min(nw).min(ne).min(sw).min(se);
+            let max = m.max(nw).max(ne).max(sw).max(se);
+
+            if max - min < self.edge_threshold_min.max(max * self.edge_threshold) {
+                return center;
+            }
+
+            let dx = -((nw + ne) - (sw + se));
+            let dy = (nw + sw) - (ne + se);
+
+            let reduce = ((nw + ne + sw + se) * 0.25 * self.reduce_mul).max(self.reduce_min);
+            let scale = 1.0 / (dx.abs().min(dy.abs()) + reduce);
+            let (dx, dy) = ((dx * scale).clamp(-self.span_max, self.span_max), (dy * scale).clamp(-self.span_max, self.span_max));
+
+            let tap = |t: f32| sample_pixel(source, px + dx * t, py + dy * t);
+
+            let a = (tap(1.0 / 3.0 - 0.5) + tap(2.0 / 3.0 - 0.5)).scale(0.5);
+            let b = a.scale(0.5) + (tap(-0.5) + tap(0.5)).scale(0.25);
+
+            let lb = luma(b);
+            let mut color = if lb < min || lb > max { a } else { b };
+            color[0][3] = center[0][3];
+            color
+        });
+    }
+}
+
+/// Adds a blurred copy of the parts brighter than `threshold` luma.
+#[derive(Clone, Copy, Debug)]
+pub struct Bloom {
+    pub threshold: f32,
+    /// Gaussian standard deviation in pixels.
+    pub sigma: f32,
+    pub intensity: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            threshold: 0.8,
+            sigma: 4.0,
+            intensity: 0.6,
+        }
+    }
+}
+
+impl Pass for Bloom {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let mut bright = to_buffer(source);
+
+        for c in bright.as_mut_slice() {
+            let luma = luma(*c);
+            let factor = if 0.0 < luma { (luma - self.threshold).max(0.0) / luma } else { 0.0 };
+            *c = c.scale(factor);
+        }
+
+        let blurred = gaussian_blur(&bright, self.sigma);
+
+        for_each_pixel(source, target, |x, y, c| {
+            let mut color = c + blurred.get([x, y].into()).scale(self.intensity);
+            color[0][3] = c[0][3];
+            color
+        });
+    }
+}
+
+/// Separable Gaussian blur.
+#[derive(Clone, Copy, Debug)]
+pub struct Blur {
+    pub sigma: f32,
+}
+
+impl Pass for Blur {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let blurred = gaussian_blur(&to_buffer(source), self.sigma);
+
+        for_each_pixel(source, target, |x, y, _| blurred.get([x, y].into()));
+    }
+}
+
+/// Sharpens by adding `amount` times the difference to a blurred copy wherever the difference exceeds `threshold`.
+#[derive(Clone, Copy, Debug)]
+pub struct UnsharpMask {
+    pub sigma: f32,
+    pub amount: f32,
+    pub threshold: f32,
+}
+
+impl Default for UnsharpMask {
+    fn default() -> Self {
+        Self {
+            sigma: 1.0,
+            amount: 0.8,
+            threshold: 0.0,
+        }
+    }
+}
+
+impl Pass for UnsharpMask {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let blurred = gaussian_blur(&to_buffer(source), self.sigma);
+
+        for_each_pixel(source, target, |x, y, c| {
+            let detail = c - blurred.get([x, y].into());
+            if luma(detail).abs() < self.threshold {
+                return c;
+            }
+
+            let mut color = c + detail.scale(self.amount);
+            color[0][3] = c[0][3];
+            color
+        });
+    }
+}
+
+/// Darkens towards the corners, `radius` and `softness` are relative to the half diagonal.
+#[derive(Clone, Copy, Debug)]
+pub struct Vignette {
+    pub strength: f32,
+    pub radius: f32,
+    pub softness: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            strength: 0.5,
+            radius: 0.75,
+            softness: 0.45,
+        }
+    }
+}
+
+impl Pass for Vignette {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let (width, height) = (source.get_width() as f32, source.get_height() as f32);
+        let half_diagonal = (width * width + height * height).sqrt() * 0.5;
+
+        for_each_pixel(source, target, |x, y, c| {
+            let dx = x as f32 + 0.5 - width * 0.5;
+            let dy = y as f32 + 0.5 - height * 0.5;
+            let distance = (dx * dx + dy * dy).sqrt() / half_diagonal;
+
+            let t = ((distance - (self.radius - self.softness)) / self.softness.max(1e-6)).clamp(0.0, 1.0);
+            let factor = 1.0 - self.strength * t * t * (3.0 - 2.0 * t);
+
+            let mut color = c.scale(factor);
+            color[0][3] = c[0][3];
+            color
+        });
+    }
+}
+
+/// Shifts red outwards and blue inwards by `strength` times the distance from the center.
+#[derive(Clone, Copy, Debug)]
+pub struct ChromaticAberration {
+    pub strength: f32,
+}
+
+impl Pass for ChromaticAberration {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        let (cx, cy) = (source.get_width() as f32 * 0.5, source.get_height() as f32 * 0.5);
+
+        for_each_pixel(source, target, |x, y, c| {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let (dx, dy) = ((px - cx) * self.strength, (py - cy) * self.strength);
+
+            let r = sample_pixel(source, px + dx, py + dy).x();
+            let b = sample_pixel(source, px - dx, py - dy).z();
+
+            [r, c.y(), b, c[0][3]].into()
+        });
+    }
+}
+
+/// 3D color lookup table, red varies fastest in `data` as in the `.cube` format.
+#[derive(Clone, Debug)]
+pub struct Lut3D {
+    pub size: usize,
+    pub domain_min: Vector3<f32>,
+    pub domain_max: Vector3<f32>,
+    pub data: Vec<Vector3<f32>>,
+}
+
+impl Lut3D {
+    pub fn load_cube<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut file = OpenOptions::new().read(true).open(path)?;
+
+        let mut content = String::new();
+
+        file.read_to_string(&mut content)?;
+
+        Self::from_cube(&content)
+    }
+
+    pub fn from_cube(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut size: usize = 0;
+        let mut domain_min: Vector3<f32> = 0.0.into();
+        let mut domain_max: Vector3<f32> = 1.0.into();
+        let mut data = Vec::new();
+
+        let parse = |words: &[&str]| -> Result<Vector3<f32>, Box<dyn Error>> {
+            match words {
+                [r, g, b] => Ok([r.parse()?, g.parse()?, b.parse()?].into()),
+                _ => Err("LUT 数据解释失败".into()),
+            }
+        };
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            match words[0] {
+                "TITLE" | "LUT_1D_INPUT_RANGE" => {}
+                "LUT_1D_SIZE" => return Err("不支持一维 LUT".into()),
+                "LUT_3D_SIZE" => size = words.get(1).ok_or("LUT 尺寸解释失败")?.parse()?,
+                "DOMAIN_MIN" => domain_min = parse(&words[1..])?,
+                "DOMAIN_MAX" => domain_max = parse(&words[1..])?,
+                "LUT_3D_INPUT_RANGE" => {
+                    let min: f32 = words.get(1).ok_or("LUT 范围解释失败")?.parse()?;
+                    let max: f32 = words.get(2).ok_or("LUT 范围解释失败")?.parse()?;
+                    domain_min = min.into();
+                    domain_max = max.into();
+                }
+                _ => data.push(parse(&words)?),
+            }
+        }
+
+        let count = size.checked_mul(size).and_then(|s| s.checked_mul(size)).ok_or("LUT 尺寸解释失败")?;
+        if 2 > size || data.len() != count {
+            return Err("LUT 尺寸与数据不一致".into());
+        }
+
+        if (0..3).any(|i| domain_min[0][i] >= domain_max[0][i]) {
+            return Err("LUT 范围无效".into());
+        }
+
+        Ok(Self { size, domain_min, domain_max, data })
+    }
+
+    /// Trilinear lookup of an RGB color.
+    pub fn lookup(&self, c: Vector3<f32>) -> Vector3<f32> {
+        let n = self.size - 1;
+
+        let coordinate = |i: usize| {
+            let t = (c[0][i] - self.domain_min[0][i]) / (self.domain_max[0][i] - self.domain_min[0][i]);
+            let f = t.clamp(0.0, 1.0) * n as f32;
+            let i0 = (f.floor() as usize).min(n);
+            (i0, (i0 + 1).min(n), f - i0 as f32)
+        };
+
+        let (r0, r1, tr) = coordinate(0);
+        let (g0, g1, tg) = coordinate(1);
+        let (b0, b1, tb) = coordinate(2);
+
+        let at = |r: usize, g: usize, b: usize| self.data[r + g * self.size + b * self.size * self.size];
+        let lerp = |a: Vector3<f32>, b: Vector3<f32>, t: f32| a.scale(1.0 - t) + b.scale(t);
+
+        let c00 = lerp(at(r0, g0, b0), at(r1, g0, b0), tr);
+        let c10 = lerp(at(r0, g1, b0), at(r1, g1, b0), tr);
+        let c01 = lerp(at(r0, g0, b1), at(r1, g0, b1), tr);
+        let c11 = lerp(at(r0, g1, b1), at(r1, g1, b1), tr);
+
+        lerp(lerp(c00, c10, tg), lerp(c01, c11, tg), tb)
+    }
+}
+
+impl Pass for Lut3D {
+    fn apply(&self, source: &dyn Image, target: &mut dyn Image) {
+        for_each_pixel(source, target, |_, _, c| {
+            let graded = self.lookup(c.into());
+            [graded.x(), graded.y(), graded.z(), c[0][3]].into()
+        });
+    }
+}
+
+pub fn copy(source: &dyn Image, target: &mut dyn Image) {
+    for y in 0..source.get_height().min(target.get_height()) {
+        for x in 0..source.get_width().min(target.get_width()) {
+            target.set_color([x, y].into(), source.get_color([x, y].into()));
+        }
+    }
+}
+
+/// Bilinear lookup in pixel units with clamped edges, pixel `(x, y)` has its center at `(x + 0.5, y + 0.5)`.
+pub fn sample_pixel(image: &dyn Image, x: f32, y: f32) -> Vector4<f32> {
+    let width = image.get_width() as i32;
+    let height = image.get_height() as i32;
+
+    let (x, y) = (x - 0.5, y - 0.5);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+
+    let texel = |x: i32, y: i32| to_float(image.get_color([x.clamp(0, width - 1) as u16, y.clamp(0, height - 1) as u16].into()));
+
+    let (x0, y0) = (x0 as i32, y0 as i32);
+
+    let bottom = texel(x0, y0).scale(1.0 - tx) + texel(x0 + 1, y0).scale(tx);
+    let top = texel(x0, y0 + 1).scale(1.0 - tx) + texel(x0 + 1, y0 + 1).scale(tx);
+
+    bottom.scale(1.0 - ty) + top.scale(ty)
+}
+
+/// Separable Gaussian with clamped edges, the kernel extends to three standard deviations.
+pub fn gaussian_blur(buffer: &Buffer<Vector4<f32>>, sigma: f32) -> Buffer<Vector4<f32>> {
+    if 0.0 >= sigma {
+        return buffer.clone();
+    }
+
+    let radius = (sigma * 3.0).ceil() as i32;
+    let weights: Vec<f32> = (-radius..=radius).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    let weights: Vec<f32> = weights.iter().map(|w| w / total).collect();
+
+    let width = buffer.get_width() as i32;
+    let height = buffer.get_height() as i32;
+
+    let pass = |source: &Buffer<Vector4<f32>>, horizontal: bool| {
+        let mut result = Buffer::new(source.get_width(), source.get_height(), Vector4::from(0.0));
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = Vector4::from(0.0);
+
+                for (k, w) in weights.iter().enumerate() {
+                    let offset = k as i32 - radius;
+                    let (sx, sy) = if horizontal { ((x + offset).clamp(0, width - 1), y) } else { (x, (y + offset).clamp(0, height - 1)) };
+                    sum += source.get([sx as u16, sy as u16].into()).scale(*w);
+                }
+
+                result.set([x as u16, y as u16].into(), sum);
+            }
+        }
+
+        result
+    };
+
+    pass(&pass(buffer, true), false)
+}
+
+/// Channels of every pixel scaled to `[0, 1]`.
+pub fn to_buffer(image: &dyn Image) -> Buffer<Vector4<f32>> {
+    let mut buffer = Buffer::new(image.get_width(), image.get_height(), Vector4::from(0.0));
+
+    for y in 0..image.get_height() {
+        for x in 0..image.get_width() {
+            buffer.set([x, y].into(), to_float(image.get_color([x, y].into())));
+        }
+    }
+
+    buffer
+}
+
+fn luma(c: Vector4<f32>) -> f32 {
+    Vector3::from(c).dot(Vector3::from(LUMA))
+}
+
+fn for_each_pixel(source: &dyn Image, target: &mut dyn Image, mut f: impl FnMut(u16, u16, Vector4<f32>) -> Vector4<f32>) {
+    for y in 0..source.get_height().min(target.get_height()) {
+        for x in 0..source.get_width().min(target.get_width()) {
+            let c = f(x, y, to_float(source.get_color([x, y].into())));
+            target.set_color([x, y].into(), to_rgba(Vector3::from(c), c[0][3]));
+        }
+    }
+}
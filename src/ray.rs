@@ -0,0 +1,396 @@
+//! <https://www.graphics.cornell.edu/pubs/1997/MT97.pdf>
+//! <https://en.wikipedia.org/wiki/Ray_tracing_(graphics)>
+//! <https://en.wikipedia.org/wiki/Schlick%27s_approximation>
+//!
+
+use std::error::Error;
+
+use crate::bvh::Bvh;
+use crate::img::Image;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Inverse;
+use crate::la::Magnitude;
+use crate::la::Matrix;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::light::Light;
+use crate::mtl::Mtl;
+use crate::obj::Obj;
+use crate::shader::interpolate;
+use crate::shader::normal_matrix;
+use crate::shader::phong;
+use crate::shader::reflect;
+use crate::shader::refract;
+use crate::shader::to_color;
+use crate::shader::transform_normal;
+use crate::shader::transform_point;
+use crate::shader::Camera;
+use crate::shader::Material;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vector3<f32>,
+    /// Unit length.
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        Self { origin, direction: direction.normal() }
+    }
+
+    pub fn at(&self, t: f32) -> Vector3<f32> {
+        self.origin + self.direction.scale(t)
+    }
+}
+
+/// Primary rays of `camera` for an image of `width` x `height` pixels, the inverse projection is computed once per frame.
+#[derive(Clone, Copy, Debug)]
+pub struct PrimaryRays {
+    eye: Vector3<f32>,
+    inverse: Matrix<f32, 4, 4>,
+    width: u16,
+    height: u16,
+}
+
+impl PrimaryRays {
+    /// `None` when the view projection of `camera` is singular.
+    pub fn new(camera: &Camera, width: u16, height: u16) -> Option<Self> {
+        let aspect = width as f32 / height as f32;
+        let inverse = (camera.view() * camera.projection(aspect)).inverse()?;
+
+        Some(Self { eye: camera.eye, inverse, width, height })
+    }
+
+    /// Ray through the point `(x, y)` in pixels, matching the rasterizer's projection of the camera.
+    pub fn ray(&self, x: f32, y: f32) -> Ray {
+        let ndc: Vector4<f32> = [x / self.width as f32 * 2.0 - 1.0, y / self.height as f32 * 2.0 - 1.0, 0.0, 1.0].into();
+        let p = ndc * self.inverse;
+
+        Ray::new(self.eye, Vector3::from([p.x() / p.w(), p.y() / p.w(), p.z() / p.w()]) - self.eye)
+    }
+}
+
+/// Möller–Trumbore, both sides of the triangle are hit, returns the distance and the barycentric weights of `v1` and `v2`.
+pub fn intersect_triangle(ray: &Ray, v0: Vector3<f32>, v1: Vector3<f32>, v2: Vector3<f32>) -> Option<(f32, f32, f32)> {
+    let e1 = v1 - v0;
+    let e2 = v2 - v0;
+
+    let p = ray.direction.cross(e2);
+    let det = e1.dot(p);
+    if 1e-12 > det.abs() {
+        return None;
+    }
+
+    let inv = 1.0 / det;
+    let s = ray.origin - v0;
+
+    let u = s.dot(p) * inv;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(e1);
+    let v = ray.direction.dot(q) * inv;
+    if 0.0 > v || 1.0 < u + v {
+        return None;
+    }
+
+    Some((e2.dot(q) * inv, u, v))
+}
+
+/// Whitted surface, `reflectivity` adds a mirror term and `transparency` splits the rest between reflection and refraction by Fresnel.
+#[derive(Clone, Copy, Debug)]
+pub struct Finish {
+    pub material: Material,
+    pub reflectivity: f32,
+    pub transparency: f32,
+    /// Index of refraction of the inside, the outside is vacuum.
+    pub ior: f32,
+    /// Radiance leaving the front side.
+    pub emission: Vector3<f32>,
+}
+
+impl Default for Finish {
+    fn default() -> Self {
+        Self {
+            material: Material::default(),
+            reflectivity: 0.0,
+            transparency: 0.0,
+            ior: 1.5,
+            emission: 0.0.into(),
+        }
+    }
+}
+
+impl From<Material> for Finish {
+    fn from(material: Material) -> Self {
+        Self { material, ..Self::default() }
+    }
+}
+
+/// `illum` 3, 5 and 7 turn the largest `Ks` channel into mirror reflectivity, `d` below 1 gives refraction with `Ni`.
+impl From<&Mtl> for Finish {
+    fn from(mtl: &Mtl) -> Self {
+        Self {
+            material: mtl.material(),
+            reflectivity: if matches!(mtl.illum, 3 | 5 | 7) { mtl.ks.x().max(mtl.ks.y()).max(mtl.ks.z()) } else { 0.0 },
+            transparency: 1.0 - mtl.d.clamp(0.0, 1.0),
+            ior: mtl.ni,
+            emission: mtl.ke,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Hit {
+    pub t: f32,
+    /// Index into `Scene::triangles`.
+    pub triangle: usize,
+    pub bar: Vector3<f32>,
+}
+
+/// World space triangles of every added `Obj`, shading attributes are kept per corner. Queries test every triangle until `build` creates the hierarchy.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub triangles: Vec<[Vector3<f32>; 3]>,
+    pub normals: Vec<[Vector3<f32>; 3]>,
+    /// Index into `finishes` per triangle.
+    pub owners: Vec<usize>,
+    pub finishes: Vec<Finish>,
+    pub lights: Vec<Light>,
+    pub bvh: Option<Bvh>,
+}
+
+impl Scene {
+    pub fn new(lights: &[Light]) -> Self {
+        Self {
+            lights: lights.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn add(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>, finish: Finish) {
+        self.finishes.push(finish);
+        self.push(obj, model, &[], self.finishes.len() - 1);
+    }
+
+    /// Faces take the finish of their `usemtl` entry in `mtls`, faces without a known material use `fallback`.
+    pub fn add_mtl(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>, mtls: &[Mtl], fallback: Finish) {
+        let base = self.finishes.len();
+
+        self.finishes.push(fallback);
+        let owners: Vec<usize> = obj
+            .materials
+            .iter()
+            .map(|name| match mtls.iter().find(|mtl| mtl.name == *name) {
+                Some(mtl) => {
+                    self.finishes.push(mtl.into());
+                    self.finishes.len() - 1
+                }
+                None => base,
+            })
+            .collect();
+
+        self.push(obj, model, &owners, base);
+    }
+
+    /// `owners` maps the material index of a face to its finish.
+    fn push(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>, owners: &[usize], fallback: usize) {
+        let normals = normal_matrix(model);
+
+        for face in 0..obj.fs.len() {
+            self.triangles.push([0, 1, 2].map(|nthvert| transform_point(obj.vert(face, nthvert), model)));
+            self.normals.push([0, 1, 2].map(|nthvert| transform_normal(obj.normal(face, nthvert), &normals)));
+            self.owners.push(obj.fs[face].material.and_then(|material| owners.get(material).copied()).unwrap_or(fallback));
+        }
+
+        self.bvh = None;
+    }
+
+    pub fn build(&mut self) {
+        self.bvh = Some(Bvh::from_triangles(&self.triangles));
+    }
+
+    /// Closest hit in `(0, t_max)`.
+    pub fn intersect(&self, ray: &Ray, t_max: f32) -> Option<Hit> {
+        if let Some(bvh) = &self.bvh {
+            let mut bar = Vector3::from(0.0);
+            let (triangle, t) = bvh.closest_hit(ray.origin, ray.direction, t_max, |triangle, t_max| {
+                let [v0, v1, v2] = self.triangles[triangle];
+                let (t, u, v) = intersect_triangle(ray, v0, v1, v2).filter(|&(t, _, _)| 0.0 < t && t < t_max)?;
+                bar = [1.0 - u - v, u, v].into();
+                Some(t)
+            })?;
+
+            return Some(Hit { t, triangle, bar });
+        }
+
+        let mut closest: Option<Hit> = None;
+
+        for (triangle, [v0, v1, v2]) in self.triangles.iter().enumerate() {
+            let Some((t, u, v)) = intersect_triangle(ray, *v0, *v1, *v2) else {
+                continue;
+            };
+
+            if 0.0 < t && t < closest.map_or(t_max, |hit| hit.t) {
+                closest = Some(Hit {
+                    t,
+                    triangle,
+                    bar: [1.0 - u - v, u, v].into(),
+                });
+            }
+        }
+
+        closest
+    }
+
+    /// Any hit in `(0, t_max)`.
+    pub fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        if let Some(bvh) = &self.bvh {
+            return bvh.any_hit(ray.origin, ray.direction, t_max, |triangle, t_max| {
+                let [v0, v1, v2] = self.triangles[triangle];
+                intersect_triangle(ray, v0, v1, v2).is_some_and(|(t, _, _)| 0.0 < t && t < t_max)
+            });
+        }
+
+        self.triangles.iter().any(|&[v0, v1, v2]| intersect_triangle(ray, v0, v1, v2).is_some_and(|(t, _, _)| 0.0 < t && t < t_max))
+    }
+
+    pub fn finish(&self, hit: &Hit) -> &Finish {
+        &self.finishes[self.owners[hit.triangle]]
+    }
+
+    /// Interpolated unit normal at `hit`, the geometric normal where the vertex normals cancel out.
+    pub fn normal(&self, hit: &Hit) -> Vector3<f32> {
+        let n = interpolate(&self.normals[hit.triangle], hit.bar);
+        if 0.0 < n.magnitude() {
+            return n.normal();
+        }
+
+        let [v0, v1, v2] = self.triangles[hit.triangle];
+        (v1 - v0).cross(v2 - v0).normal()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RayTracer {
+    /// Secondary bounces, 0 gives the direct lighting of the rasterizer.
+    pub max_depth: u32,
+    pub shadows: bool,
+    pub blinn: bool,
+    pub background: Vector3<f32>,
+    /// Offset of secondary ray origins along the geometric normal.
+    pub bias: f32,
+}
+
+impl Default for RayTracer {
+    fn default() -> Self {
+        Self {
+            max_depth: 5,
+            shadows: true,
+            blinn: true,
+            background: 0.0.into(),
+            bias: 1e-4,
+        }
+    }
+}
+
+impl RayTracer {
+    /// Writes the pixels whose primary ray hits the scene.
+    pub fn render<I: Image>(&self, scene: &Scene, camera: &Camera, image: &mut I) -> Result<(), Box<dyn Error>> {
+        let width = image.get_width();
+        let height = image.get_height();
+        let primary = PrimaryRays::new(camera, width, height).ok_or("相机矩阵不可逆")?;
+
+        for y in 0..height {
+            for x in 0..width {
+                let ray = primary.ray(x as f32 + 0.5, y as f32 + 0.5);
+
+                if let Some(hit) = scene.intersect(&ray, f32::INFINITY) {
+                    image.set_color([x, y].into(), to_color(self.shade(scene, &ray, &hit, 0)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn trace(&self, scene: &Scene, ray: &Ray, depth: u32) -> Vector3<f32> {
+        match scene.intersect(ray, f32::INFINITY) {
+            Some(hit) => self.shade(scene, ray, &hit, depth),
+            None => self.background,
+        }
+    }
+
+    fn shade(&self, scene: &Scene, ray: &Ray, hit: &Hit, depth: u32) -> Vector3<f32> {
+        let finish = scene.finish(hit);
+        let p = ray.at(hit.t);
+
+        let [v0, v1, v2] = scene.triangles[hit.triangle];
+        let geometric = (v1 - v0).cross(v2 - v0).normal();
+        let entering = 0.0 > geometric.dot(ray.direction);
+        let outward = if entering { geometric } else { geometric.scale(-1.0) };
+
+        let mut n = scene.normal(hit);
+        if 0.0 < n.dot(ray.direction) {
+            n = n.scale(-1.0);
+        }
+
+        let v = ray.direction.scale(-1.0);
+        let mut local = finish.material.ambient;
+        if entering {
+            local += finish.emission;
+        }
+
+        for light in &scene.lights {
+            light.illuminate(p, |sample| {
+                if self.shadows && scene.occluded(&Ray::new(p + outward.scale(self.bias), sample.direction), sample.distance) {
+                    return;
+                }
+
+                local += sample.radiance.hadamard(phong(&finish.material, n, sample.direction, v, self.blinn));
+            });
+        }
+
+        if depth >= self.max_depth {
+            return local;
+        }
+
+        let mut color = local.scale(1.0 - finish.transparency);
+        let mut reflection = finish.reflectivity;
+
+        if 0.0 < finish.transparency {
+            let eta = if entering { 1.0 / finish.ior } else { finish.ior };
+            let r0 = ((1.0 - finish.ior) / (1.0 + finish.ior)).powi(2);
+
+            let fresnel = match refract(ray.direction, n, eta) {
+                Some(direction) => {
+                    let cos = if entering { v.dot(n) } else { direction.dot(n.scale(-1.0)) };
+                    let fresnel = r0 + (1.0 - r0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5);
+
+                    let transmitted = self.trace(scene, &Ray::new(p - outward.scale(self.bias), direction), depth + 1);
+                    color += transmitted.scale(finish.transparency * (1.0 - fresnel));
+                    fresnel
+                }
+                None => 1.0,
+            };
+
+            reflection += finish.transparency * fresnel;
+        }
+
+        if 0.0 < reflection {
+            color += self.trace(scene, &Ray::new(p + outward.scale(self.bias), reflect(ray.direction, n)), depth + 1).scale(reflection);
+        }
+
+        color
+    }
+}
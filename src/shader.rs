@@ -0,0 +1,418 @@
+//! <https://en.wikipedia.org/wiki/Phong_reflection_model>
+//! <https://en.wikipedia.org/wiki/Blinn%E2%80%93Phong_reflection_model>
+//!
+
+use std::f32::consts::PI;
+
+use crate::fog::Fog;
+use crate::gl::draw;
+use crate::gl::embed;
+use crate::gl::infinite_perspective;
+use crate::gl::lookat;
+use crate::gl::perspective;
+use crate::gl::Fragment;
+use crate::gl::RenderState;
+use crate::gl::Shader;
+use crate::img::DepthBuffer;
+use crate::img::sample;
+use crate::img::Image;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Identity;
+use crate::la::Inverse;
+use crate::la::Matrix;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Transpose;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::light::Light;
+use crate::obj::Obj;
+use crate::shadow::Shadow;
+use crate::ssao::OcclusionBuffer;
+use crate::tga::TGAColor;
+use crate::tga::TGAImage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShadingModel {
+    Flat,
+    Gouraud,
+    Phong,
+    BlinnPhong,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    pub shininess: f32,
+    /// Alpha written with the shaded color.
+    pub opacity: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            ambient: 0.1.into(),
+            diffuse: 0.8.into(),
+            specular: 0.5.into(),
+            shininess: 32.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub eye: Vector3<f32>,
+    pub center: Vector3<f32>,
+    pub up: Vector3<f32>,
+    pub fovy: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            eye: [0.0, 0.0, 3.0].into(),
+            center: [0.0, 0.0, 0.0].into(),
+            up: [0.0, 1.0, 0.0].into(),
+            fovy: PI / 4.0,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn view(&self) -> Matrix<f32, 4, 4> {
+        lookat(self.eye, self.center, self.up)
+    }
+
+    /// An infinite `far` selects `infinite_perspective`.
+    pub fn projection(&self, aspect: f32) -> Matrix<f32, 4, 4> {
+        if self.far.is_infinite() {
+            infinite_perspective(self.fovy, aspect, self.near)
+        } else {
+            perspective(self.fovy, aspect, self.near, self.far)
+        }
+    }
+}
+
+/// Upper left 3x3 of the inverse transpose of `model`, for transforming row vector normals.
+pub fn normal_matrix(model: &Matrix<f32, 4, 4>) -> Matrix<f32, 3, 3> {
+    let mut linear = Matrix::<f32, 3, 3>::identity();
+    for r in 0..3 {
+        for c in 0..3 {
+            linear[r][c] = model[r][c];
+        }
+    }
+
+    linear.inverse().unwrap_or(linear).transpose()
+}
+
+pub fn transform_point(p: Vector3<f32>, m: &Matrix<f32, 4, 4>) -> Vector3<f32> {
+    (embed(p, 1.0) * m).into()
+}
+
+pub fn transform_normal(n: Vector3<f32>, m: &Matrix<f32, 3, 3>) -> Vector3<f32> {
+    (n * m).normal()
+}
+
+pub fn reflect(i: Vector3<f32>, n: Vector3<f32>) -> Vector3<f32> {
+    i - n.scale(2.0 * i.dot(n))
+}
+
+/// Snell refraction of the unit incident `i` through the unit normal `n` with `eta` the ratio of indices, `None` on total internal reflection.
+pub fn refract(i: Vector3<f32>, n: Vector3<f32>, eta: f32) -> Option<Vector3<f32>> {
+    let cos = -i.dot(n);
+    let k = 1.0 - eta * eta * (1.0 - cos * cos);
+
+    if 0.0 > k {
+        None
+    } else {
+        Some(i.scale(eta) + n.scale(eta * cos - k.sqrt()))
+    }
+}
+
+/// Diffuse and specular terms for unit vectors `n` (normal), `l` (towards the light) and `v` (towards the eye).
+pub fn phong(material: &Material, n: Vector3<f32>, l: Vector3<f32>, v: Vector3<f32>, blinn: bool) -> Vector3<f32> {
+    let diffuse = n.dot(l).max(0.0);
+
+    let specular = if 0.0 >= diffuse {
+        0.0
+    } else if blinn {
+        n.dot((l + v).normal()).max(0.0).powf(material.shininess)
+    } else {
+        reflect(l.scale(-1.0), n).dot(v).max(0.0).powf(material.shininess)
+    };
+
+    material.diffuse.scale(diffuse) + material.specular.scale(specular)
+}
+
+/// Ambient term plus the contribution of every sample of every light reaching `p`, scaled by the light's shadow visibility.
+pub fn shade(material: &Material, uniforms: &Uniforms, p: Vector3<f32>, n: Vector3<f32>, blinn: bool) -> Vector3<f32> {
+    let v = (uniforms.eye - p).normal();
+    let mut color = material.ambient;
+
+    for (i, light) in uniforms.lights.iter().enumerate() {
+        let visibility = uniforms.visibility(i, p, n);
+
+        if 0.0 >= visibility {
+            continue;
+        }
+
+        light.illuminate(p, |sample| color += sample.radiance.hadamard(phong(material, n, sample.direction, v, blinn)).scale(visibility));
+    }
+
+    color
+}
+
+pub fn to_color(c: Vector3<f32>) -> TGAColor {
+    to_rgba(c, 1.0)
+}
+
+pub fn to_rgba(c: Vector3<f32>, alpha: f32) -> TGAColor {
+    let quantize = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+
+    [quantize(c.x()), quantize(c.y()), quantize(c.z()), quantize(alpha)].into()
+}
+
+/// Per draw state shared by the built-in shaders.
+#[derive(Clone, Debug)]
+pub struct Uniforms {
+    pub model: Matrix<f32, 4, 4>,
+    pub view_projection: Matrix<f32, 4, 4>,
+    pub normals: Matrix<f32, 3, 3>,
+    pub eye: Vector3<f32>,
+    pub lights: Vec<Light>,
+    /// Indexed like `lights`, missing entries are unshadowed.
+    pub shadows: Vec<Option<Shadow>>,
+    /// Screen space factor applied to the ambient term.
+    pub occlusion: Option<OcclusionBuffer>,
+    pub fog: Option<Fog>,
+}
+
+impl Uniforms {
+    pub fn new(model: Matrix<f32, 4, 4>, camera: &Camera, aspect: f32, lights: &[Light]) -> Self {
+        Self {
+            model,
+            view_projection: camera.view() * camera.projection(aspect),
+            normals: normal_matrix(&model),
+            eye: camera.eye,
+            lights: lights.to_vec(),
+            shadows: Vec::new(),
+            occlusion: None,
+            fog: None,
+        }
+    }
+
+    pub fn ambient_occlusion(&self, fragment: &Fragment) -> f32 {
+        match &self.occlusion {
+            Some(occlusion) => occlusion.get([fragment.x as u16, fragment.y as u16].into()),
+            None => 1.0,
+        }
+    }
+
+    /// Shadow visibility of `lights[i]` at `p` with normal `n`, `1` for unshadowed lights.
+    pub fn visibility(&self, i: usize, p: Vector3<f32>, n: Vector3<f32>) -> f32 {
+        match self.shadows.get(i) {
+            Some(Some(shadow)) => shadow.visibility(p, n),
+            _ => 1.0,
+        }
+    }
+
+    /// Blends `color` seen at `p` towards the fog color.
+    pub fn fog(&self, color: Vector3<f32>, p: Vector3<f32>) -> Vector3<f32> {
+        match &self.fog {
+            Some(fog) => fog.apply(color, self.eye, p),
+            None => color,
+        }
+    }
+}
+
+pub struct LightingShader<'a> {
+    pub obj: &'a Obj,
+    pub uniforms: Uniforms,
+    pub material: Material,
+    pub shading: ShadingModel,
+    varying_position: [Vector3<f32>; 3],
+    varying_normal: [Vector3<f32>; 3],
+    varying_color: [Vector3<f32>; 3],
+}
+
+impl<'a> LightingShader<'a> {
+    pub fn new(obj: &'a Obj, uniforms: Uniforms, material: Material, shading: ShadingModel) -> Self {
+        Self {
+            obj,
+            uniforms,
+            material,
+            shading,
+            varying_position: [0.0.into(); 3],
+            varying_normal: [0.0.into(); 3],
+            varying_color: [0.0.into(); 3],
+        }
+    }
+
+    fn shade(&self, position: Vector3<f32>, normal: Vector3<f32>) -> Vector3<f32> {
+        shade(&self.material, &self.uniforms, position, normal, ShadingModel::BlinnPhong == self.shading)
+    }
+}
+
+impl Shader for LightingShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let position = transform_point(self.obj.vert(face, nthvert), &self.uniforms.model);
+        let normal = transform_normal(self.obj.normal(face, nthvert), &self.uniforms.normals);
+
+        self.varying_position[nthvert] = position;
+        self.varying_normal[nthvert] = normal;
+
+        if ShadingModel::Gouraud == self.shading {
+            self.varying_color[nthvert] = self.shade(position, normal);
+        }
+
+        embed(position, 1.0) * self.uniforms.view_projection
+    }
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor> {
+        let bar = fragment.bar;
+
+        let color = match self.shading {
+            ShadingModel::Flat => {
+                let [p0, p1, p2] = self.varying_position;
+                let normal = (p1 - p0).cross(p2 - p0).normal();
+                self.shade((p0 + p1 + p2).scale(1.0 / 3.0), normal)
+            }
+            ShadingModel::Gouraud => interpolate(&self.varying_color, bar),
+            ShadingModel::Phong | ShadingModel::BlinnPhong => self.shade(interpolate(&self.varying_position, bar), interpolate(&self.varying_normal, bar).normal()),
+        };
+
+        let color = color + self.material.ambient.scale(self.uniforms.ambient_occlusion(fragment) - 1.0);
+        let color = self.uniforms.fog(color, interpolate(&self.varying_position, bar));
+
+        Some(to_rgba(color, self.material.opacity))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalSpace {
+    Tangent,
+    Object,
+}
+
+/// Rows are tangent, bitangent and normal, so `n * tbn` takes a tangent space row vector into the space of `normal`.
+pub fn tbn(tangent: Vector4<f32>, normal: Vector3<f32>) -> Matrix<f32, 3, 3> {
+    let n = normal.normal();
+    let t = Vector3::from(tangent);
+    let t = (t - n.scale(n.dot(t))).normal();
+    let b = n.cross(t).scale(tangent.w());
+
+    [[t.x(), t.y(), t.z()], [b.x(), b.y(), b.z()], [n.x(), n.y(), n.z()]].into()
+}
+
+/// Blinn-Phong shading with normals taken from `normal_map`, `diffuse_map` modulates the diffuse color when present.
+pub struct NormalMapShader<'a> {
+    pub obj: &'a Obj,
+    pub uniforms: Uniforms,
+    pub material: Material,
+    pub normal_map: &'a TGAImage,
+    pub space: NormalSpace,
+    pub diffuse_map: Option<&'a TGAImage>,
+    tangents: Vec<[Vector4<f32>; 3]>,
+    varying_position: [Vector3<f32>; 3],
+    varying_normal: [Vector3<f32>; 3],
+    varying_tangent: [Vector4<f32>; 3],
+    varying_uv: [Vector2<f32>; 3],
+}
+
+impl<'a> NormalMapShader<'a> {
+    pub fn new(obj: &'a Obj, uniforms: Uniforms, material: Material, normal_map: &'a TGAImage, space: NormalSpace) -> Self {
+        Self {
+            obj,
+            uniforms,
+            material,
+            normal_map,
+            space,
+            diffuse_map: None,
+            tangents: if NormalSpace::Tangent == space { obj.tangents() } else { Vec::new() },
+            varying_position: [0.0.into(); 3],
+            varying_normal: [0.0.into(); 3],
+            varying_tangent: [0.0.into(); 3],
+            varying_uv: [0.0.into(); 3],
+        }
+    }
+}
+
+impl Shader for NormalMapShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let position = transform_point(self.obj.vert(face, nthvert), &self.uniforms.model);
+
+        self.varying_position[nthvert] = position;
+        self.varying_normal[nthvert] = transform_normal(self.obj.normal(face, nthvert), &self.uniforms.normals);
+        self.varying_uv[nthvert] = self.obj.uv(face, nthvert);
+
+        if let Some(tangents) = self.tangents.get(face) {
+            let tangent = tangents[nthvert];
+            let t: Vector3<f32> = (embed(tangent.into(), 0.0) * self.uniforms.model).into();
+            self.varying_tangent[nthvert] = embed(t, tangent.w());
+        }
+
+        embed(position, 1.0) * self.uniforms.view_projection
+    }
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor> {
+        let bar = fragment.bar;
+
+        let position = interpolate(&self.varying_position, bar);
+        let uv = self.varying_uv[0].scale(bar.x()) + self.varying_uv[1].scale(bar.y()) + self.varying_uv[2].scale(bar.z());
+
+        let texel = sample(self.normal_map, uv);
+        let mapped: Vector3<f32> = [texel.x() * 2.0 - 1.0, texel.y() * 2.0 - 1.0, texel.z() * 2.0 - 1.0].into();
+
+        let normal = match self.space {
+            NormalSpace::Tangent => {
+                let tangent = self.varying_tangent[0].scale(bar.x()) + self.varying_tangent[1].scale(bar.y()) + self.varying_tangent[2].scale(bar.z());
+                let tangent = embed(tangent.into(), if 0.0 > tangent.w() { -1.0 } else { 1.0 });
+                (mapped * tbn(tangent, interpolate(&self.varying_normal, bar))).normal()
+            }
+            NormalSpace::Object => transform_normal(mapped, &self.uniforms.normals),
+        };
+
+        let mut material = self.material;
+        material.ambient = material.ambient.scale(self.uniforms.ambient_occlusion(fragment));
+        if let Some(diffuse_map) = self.diffuse_map {
+            material.diffuse = material.diffuse.hadamard(Vector3::from(sample(diffuse_map, uv)));
+        }
+
+        let color = shade(&material, &self.uniforms, position, normal, true);
+        let color = self.uniforms.fog(color, position);
+
+        Some(to_rgba(color, material.opacity))
+    }
+}
+
+pub fn interpolate(vs: &[Vector3<f32>; 3], bar: Vector3<f32>) -> Vector3<f32> {
+    vs[0].scale(bar.x()) + vs[1].scale(bar.y()) + vs[2].scale(bar.z())
+}
+
+/// Renders `obj` with a white directional light shining from `light_dir` towards the origin.
+pub fn render<I: Image>(obj: &Obj, image: &mut I, camera: &Camera, light_dir: Vector3<f32>, shading: ShadingModel, material: &Material) {
+    render_lights(obj, image, camera, &[Light::directional(light_dir.scale(-1.0))], shading, material);
+}
+
+pub fn render_lights<I: Image>(obj: &Obj, image: &mut I, camera: &Camera, lights: &[Light], shading: ShadingModel, material: &Material) {
+    let aspect = image.get_width() as f32 / image.get_height() as f32;
+
+    let mut shader = LightingShader::new(obj, Uniforms::new(Matrix::identity(), camera, aspect, lights), *material, shading);
+    let mut depth = DepthBuffer::new(image.get_width(), image.get_height(), f32::MAX);
+
+    draw(obj.fs.len(), &mut shader, image, &mut depth, &RenderState::default());
+}
@@ -0,0 +1,320 @@
+//! <https://learnopengl.com/Advanced-Lighting/Shadows/Shadow-Mapping>
+//! <https://developer.nvidia.com/gpugems/gpugems3/part-ii-light-and-shadows/chapter-10-parallel-split-shadow-maps-programmable-gpus>
+//!
+
+use std::f32::consts::FRAC_PI_2;
+
+use crate::gl::embed;
+use crate::gl::is_front_facing;
+use crate::gl::lookat;
+use crate::gl::orthographic;
+use crate::gl::perspective;
+use crate::gl::rasterize;
+use crate::gl::CullMode;
+use crate::img::DepthBuffer;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Magnitude;
+use crate::la::Matrix;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::obj::Obj;
+use crate::shader::transform_point;
+use crate::shader::Camera;
+
+/// Depth as seen from a light, `bias + slope_bias * tan(angle)` is subtracted before comparing and `pcf` is the filter radius in texels.
+#[derive(Clone, Debug)]
+pub struct ShadowMap {
+    pub depth: DepthBuffer,
+    pub light_matrix: Matrix<f32, 4, 4>,
+    pub light_position: Option<Vector3<f32>>,
+    pub light_direction: Vector3<f32>,
+    pub bias: f32,
+    pub slope_bias: f32,
+    pub pcf: u32,
+    pub cull: CullMode,
+}
+
+impl ShadowMap {
+    pub fn new(view: Matrix<f32, 4, 4>, projection: Matrix<f32, 4, 4>, size: u16) -> Self {
+        Self {
+            depth: DepthBuffer::new(size, size, f32::MAX),
+            light_matrix: view * projection,
+            light_position: None,
+            light_direction: [0.0, 0.0, -1.0].into(),
+            bias: 0.001,
+            slope_bias: 0.002,
+            pcf: 1,
+            cull: CullMode::None,
+        }
+    }
+
+    /// Orthographic map of a directional light covering the sphere at `center` with `radius`.
+    pub fn directional(direction: Vector3<f32>, center: Vector3<f32>, radius: f32, size: u16) -> Self {
+        let direction = direction.normal();
+        let eye = center - direction.scale(2.0 * radius);
+
+        let mut map = Self::new(lookat(eye, center, up_for(direction)), orthographic(-radius, radius, -radius, radius, radius * 0.01, 4.0 * radius), size);
+        map.light_direction = direction;
+        map
+    }
+
+    /// Perspective map of a spot light, `fov` is the full cone angle.
+    pub fn spot(position: Vector3<f32>, direction: Vector3<f32>, fov: f32, near: f32, far: f32, size: u16) -> Self {
+        let direction = direction.normal();
+
+        let mut map = Self::new(lookat(position, position + direction, up_for(direction)), perspective(fov, 1.0, near, far), size);
+        map.light_position = Some(position);
+        map.light_direction = direction;
+        map
+    }
+
+    pub fn clear(&mut self) {
+        self.depth.fill(f32::MAX);
+    }
+
+    pub fn render(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>) {
+        let size = self.depth.get_width();
+
+        for face in 0..obj.fs.len() {
+            let clip = [0, 1, 2].map(|nthvert| embed(transform_point(obj.vert(face, nthvert), model), 1.0) * self.light_matrix);
+
+            match self.cull {
+                CullMode::Back if !is_front_facing(&clip) => continue,
+                CullMode::Front if is_front_facing(&clip) => continue,
+                _ => {}
+            }
+
+            // directional maps clamp casters between the light and the near plane to depth 0 instead of clipping them,
+            // z is squeezed towards the far plane for rasterization and stretched back per fragment
+            let near = clip.iter().map(|v| *v.z()).fold(f32::MAX, f32::min);
+            let scale = if self.light_position.is_none() && -1.0 > near { (1.0 - near) * 0.51 } else { 1.0 };
+            let clip = clip.map(|v| [v.x(), v.y(), (v.z() - v.w()) / scale + v.w(), v.w()].into());
+
+            let depth = &mut self.depth;
+            rasterize(&clip, size, size, |fragment| {
+                let coord = [fragment.x as u16, fragment.y as u16].into();
+                let z = (1.0 - (1.0 - fragment.depth) * scale).max(0.0);
+                if z < depth.get(coord) {
+                    depth.set(coord, z);
+                }
+            });
+        }
+    }
+
+    /// Fraction of the PCF kernel that sees the light from `p` with surface normal `n`, points outside the map are lit.
+    pub fn visibility(&self, p: Vector3<f32>, n: Vector3<f32>) -> f32 {
+        let clip: Vector4<f32> = embed(p, 1.0) * self.light_matrix;
+        if 0.0 >= clip.w() {
+            return 1.0;
+        }
+
+        let size = self.depth.get_width() as i32;
+
+        let x = (clip.x() / clip.w() + 1.0) * 0.5 * size as f32;
+        let y = (clip.y() / clip.w() + 1.0) * 0.5 * size as f32;
+        let z = (clip.z() / clip.w()) * 0.5 + 0.5;
+
+        if !(0.0..=1.0).contains(&z) {
+            return 1.0;
+        }
+
+        let l = match self.light_position {
+            Some(position) => (position - p).normal(),
+            None => self.light_direction.scale(-1.0),
+        };
+        let cos = n.normal().dot(l).clamp(1e-3, 1.0);
+        let tan = ((1.0 - cos * cos).sqrt() / cos).min(10.0);
+        let reference = z - self.bias - self.slope_bias * tan;
+
+        let (cx, cy) = (x.floor() as i32, y.floor() as i32);
+        if 0 > cx || 0 > cy || cx >= size || cy >= size {
+            return 1.0;
+        }
+
+        let radius = self.pcf as i32;
+
+        let mut lit = 0;
+        let mut total = 0;
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let sx = (cx + dx).clamp(0, size - 1);
+                let sy = (cy + dy).clamp(0, size - 1);
+                total += 1;
+
+                if reference <= self.depth.get([sx as u16, sy as u16].into()) {
+                    lit += 1;
+                }
+            }
+        }
+
+        lit as f32 / total as f32
+    }
+}
+
+/// Splits the view frustum of `camera` up to `max_distance` into orthographic cascades for a directional light.
+#[derive(Clone, Debug)]
+pub struct CascadedShadowMap {
+    pub cascades: Vec<ShadowMap>,
+    pub splits: Vec<f32>,
+    pub eye: Vector3<f32>,
+    pub forward: Vector3<f32>,
+}
+
+impl CascadedShadowMap {
+    /// `lambda` blends the logarithmic (1) and uniform (0) split schemes.
+    pub fn new(direction: Vector3<f32>, camera: &Camera, aspect: f32, max_distance: f32, count: usize, lambda: f32, size: u16) -> Self {
+        let count = count.max(1);
+        let near = camera.near;
+        let far = max_distance.min(camera.far);
+
+        let forward = (camera.center - camera.eye).normal();
+        let right = forward.cross(camera.up).normal();
+        let up = right.cross(forward);
+
+        let tan_y = (camera.fovy * 0.5).tan();
+        let tan_x = tan_y * aspect;
+
+        let mut cascades = Vec::with_capacity(count);
+        let mut splits = Vec::with_capacity(count);
+
+        let mut previous = near;
+
+        for i in 1..=count {
+            let t = i as f32 / count as f32;
+            let split = lambda * near * (far / near).powf(t) + (1.0 - lambda) * (near + (far - near) * t);
+
+            let mut corners = Vec::with_capacity(8);
+            for d in [previous, split] {
+                for (sx, sy) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                    corners.push(camera.eye + forward.scale(d) + right.scale(sx * tan_x * d) + up.scale(sy * tan_y * d));
+                }
+            }
+
+            let center = corners.iter().fold(Vector3::<f32>::from(0.0), |acc, c| acc + *c).scale(1.0 / 8.0);
+            let radius = corners.iter().map(|c| (*c - center).magnitude()).fold(0.0, f32::max);
+
+            cascades.push(ShadowMap::directional(direction, center, radius, size));
+            splits.push(split);
+
+            previous = split;
+        }
+
+        Self {
+            cascades,
+            splits,
+            eye: camera.eye,
+            forward,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cascades.iter_mut().for_each(ShadowMap::clear);
+    }
+
+    pub fn render(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>) {
+        self.cascades.iter_mut().for_each(|cascade| cascade.render(obj, model));
+    }
+
+    pub fn visibility(&self, p: Vector3<f32>, n: Vector3<f32>) -> f32 {
+        let distance = (p - self.eye).dot(self.forward);
+
+        match self.splits.iter().position(|&split| distance <= split) {
+            Some(i) => self.cascades[i].visibility(p, n),
+            None => 1.0,
+        }
+    }
+}
+
+/// Six 90 degree perspective maps around a point light, ordered +X, -X, +Y, -Y, +Z, -Z.
+#[derive(Clone, Debug)]
+pub struct CubeShadowMap {
+    pub faces: Vec<ShadowMap>,
+    pub position: Vector3<f32>,
+}
+
+impl CubeShadowMap {
+    pub fn new(position: Vector3<f32>, near: f32, far: f32, size: u16) -> Self {
+        let directions: [[f32; 3]; 6] = [[1.0, 0.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, -1.0]];
+
+        let faces = directions
+            .iter()
+            .map(|&d| ShadowMap::spot(position, d.into(), FRAC_PI_2, near, far, size))
+            .collect();
+
+        Self { faces, position }
+    }
+
+    pub fn clear(&mut self) {
+        self.faces.iter_mut().for_each(ShadowMap::clear);
+    }
+
+    pub fn render(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>) {
+        self.faces.iter_mut().for_each(|face| face.render(obj, model));
+    }
+
+    pub fn visibility(&self, p: Vector3<f32>, n: Vector3<f32>) -> f32 {
+        let d = p - self.position;
+        let (ax, ay, az) = (d.x().abs(), d.y().abs(), d.z().abs());
+
+        let face = if ax >= ay && ax >= az {
+            if 0.0 <= d.x() { 0 } else { 1 }
+        } else if ay >= az {
+            if 0.0 <= d.y() { 2 } else { 3 }
+        } else if 0.0 <= d.z() {
+            4
+        } else {
+            5
+        };
+
+        self.faces[face].visibility(p, n)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Shadow {
+    Map(ShadowMap),
+    Cascaded(CascadedShadowMap),
+    Cube(CubeShadowMap),
+}
+
+impl Shadow {
+    pub fn clear(&mut self) {
+        match self {
+            Shadow::Map(map) => map.clear(),
+            Shadow::Cascaded(map) => map.clear(),
+            Shadow::Cube(map) => map.clear(),
+        }
+    }
+
+    pub fn render(&mut self, obj: &Obj, model: &Matrix<f32, 4, 4>) {
+        match self {
+            Shadow::Map(map) => map.render(obj, model),
+            Shadow::Cascaded(map) => map.render(obj, model),
+            Shadow::Cube(map) => map.render(obj, model),
+        }
+    }
+
+    pub fn visibility(&self, p: Vector3<f32>, n: Vector3<f32>) -> f32 {
+        match self {
+            Shadow::Map(map) => map.visibility(p, n),
+            Shadow::Cascaded(map) => map.visibility(p, n),
+            Shadow::Cube(map) => map.visibility(p, n),
+        }
+    }
+}
+
+fn up_for(direction: Vector3<f32>) -> Vector3<f32> {
+    if 0.99 < direction.y().abs() {
+        [0.0, 0.0, 1.0].into()
+    } else {
+        [0.0, 1.0, 0.0].into()
+    }
+}
@@ -0,0 +1,215 @@
+//! <https://www.scratchapixel.com/lessons/procedural-generation-virtual-worlds/simulating-sky/simulating-colors-of-the-sky.html>
+//! <https://developer.nvidia.com/gpugems/gpugems2/part-ii-shading-lighting-and-shadows/chapter-16-accurate-atmospheric-scattering>
+//!
+
+use std::f32::consts::PI;
+
+use crate::cubemap::fill_background;
+use crate::img::Buffer;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::pbr::equirect_direction;
+use crate::shader::Camera;
+use crate::tga::TGAColor;
+
+/// Single scattering Rayleigh and Mie atmosphere seen from just above the ground, distances are in meters and `y` is up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sky {
+    /// Towards the sun.
+    pub sun_direction: Vector3<f32>,
+    pub sun_intensity: f32,
+    /// Angular radius of the drawn sun disk, 0 hides it.
+    pub sun_radius: f32,
+    pub planet_radius: f32,
+    pub atmosphere_radius: f32,
+    pub altitude: f32,
+    pub rayleigh: Vector3<f32>,
+    pub rayleigh_height: f32,
+    pub mie: f32,
+    pub mie_height: f32,
+    /// Mie phase asymmetry.
+    pub mie_g: f32,
+    pub samples: u32,
+    pub light_samples: u32,
+}
+
+impl Default for Sky {
+    fn default() -> Self {
+        Self {
+            sun_direction: [0.0, 0.3, -1.0].into(),
+            sun_intensity: 20.0,
+            sun_radius: 0.00465,
+            planet_radius: 6360e3,
+            atmosphere_radius: 6420e3,
+            altitude: 1.0,
+            rayleigh: [5.8e-6, 13.5e-6, 33.1e-6].into(),
+            rayleigh_height: 7994.0,
+            mie: 21e-6,
+            mie_height: 1200.0,
+            mie_g: 0.76,
+            samples: 16,
+            light_samples: 8,
+        }
+    }
+}
+
+impl Sky {
+    pub fn new(sun_direction: Vector3<f32>) -> Self {
+        Self {
+            sun_direction: sun_direction.normal(),
+            ..Self::default()
+        }
+    }
+
+    /// Sky radiance arriving from `direction`, ground hits only carry the in-scattering in front of them.
+    pub fn radiance(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        let direction = direction.normal();
+        let sun = self.sun_direction.normal();
+        let origin: Vector3<f32> = [0.0, self.planet_radius + self.altitude, 0.0].into();
+
+        let Some((_, far)) = intersect_sphere(origin, direction, self.atmosphere_radius) else {
+            return 0.0.into();
+        };
+        let length = match intersect_sphere(origin, direction, self.planet_radius) {
+            Some((near, _)) if 0.0 < near => near,
+            _ => far,
+        };
+
+        let samples = self.samples.max(1);
+        let step = length / samples as f32;
+
+        let mut rayleigh_sum = Vector3::from(0.0);
+        let mut mie_sum = Vector3::from(0.0);
+        let mut depth = Vector2::<f32>::from(0.0);
+
+        for i in 0..samples {
+            let p = origin + direction.scale((i as f32 + 0.5) * step);
+            let density = self.density(p).scale(step);
+            depth += density;
+
+            let Some(light_depth) = self.optical_depth(p, sun) else {
+                continue;
+            };
+
+            let attenuation = self.extinction(depth + light_depth);
+            rayleigh_sum += attenuation.scale(density.x());
+            mie_sum += attenuation.scale(density.y());
+        }
+
+        let mu = direction.dot(sun);
+        let rayleigh_phase = 3.0 / (16.0 * PI) * (1.0 + mu * mu);
+        let g = self.mie_g;
+        let mie_phase = 3.0 / (8.0 * PI) * ((1.0 - g * g) * (1.0 + mu * mu)) / ((2.0 + g * g) * (1.0 + g * g - 2.0 * g * mu).powf(1.5));
+
+        let mut color = (rayleigh_sum.hadamard(self.rayleigh).scale(rayleigh_phase) + mie_sum.scale(self.mie * mie_phase)).scale(self.sun_intensity);
+
+        if 0.0 < self.sun_radius && mu > self.sun_radius.cos() && length == far {
+            color += self.sun_radiance().scale(1.0 / (PI * self.sun_radius * self.sun_radius));
+        }
+
+        color
+    }
+
+    /// Sun light after passing through the atmosphere, suitable as a directional light color.
+    pub fn sun_radiance(&self) -> Vector3<f32> {
+        let origin: Vector3<f32> = [0.0, self.planet_radius + self.altitude, 0.0].into();
+
+        match self.optical_depth(origin, self.sun_direction.normal()) {
+            Some(depth) => self.extinction(depth).scale(self.sun_intensity),
+            None => 0.0.into(),
+        }
+    }
+
+    /// Equirectangular radiance, usable as `Environment::from_radiance` input.
+    pub fn to_radiance(&self, width: u16, height: u16) -> Buffer<Vector3<f32>> {
+        let mut radiance = Buffer::new(width, height, Vector3::from(0.0));
+
+        for y in 0..height {
+            for x in 0..width {
+                let uv = [(x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32].into();
+                radiance.set([x, y].into(), self.radiance(equirect_direction(uv)));
+            }
+        }
+
+        radiance
+    }
+
+    /// Rayleigh and Mie densities relative to sea level.
+    fn density(&self, p: Vector3<f32>) -> Vector2<f32> {
+        let height = p.dot(p).sqrt() - self.planet_radius;
+
+        [(-height / self.rayleigh_height).exp(), (-height / self.mie_height).exp()].into()
+    }
+
+    /// `None` when the planet blocks the path to the top of the atmosphere.
+    fn optical_depth(&self, p: Vector3<f32>, direction: Vector3<f32>) -> Option<Vector2<f32>> {
+        let (_, far) = intersect_sphere(p, direction, self.atmosphere_radius)?;
+
+        let samples = self.light_samples.max(1);
+        let step = far / samples as f32;
+
+        let mut depth = Vector2::from(0.0);
+        for i in 0..samples {
+            let q = p + direction.scale((i as f32 + 0.5) * step);
+            if self.planet_radius > q.dot(q).sqrt() {
+                return None;
+            }
+            depth += self.density(q).scale(step);
+        }
+
+        Some(depth)
+    }
+
+    fn extinction(&self, depth: Vector2<f32>) -> Vector3<f32> {
+        let tau = self.rayleigh.scale(depth.x()) + Vector3::from(self.mie * 1.1 * depth.y());
+
+        [(-tau.x()).exp(), (-tau.y()).exp(), (-tau.z()).exp()].into()
+    }
+}
+
+/// Ray distances to a sphere centered at the origin, the first one is negative when `origin` is inside. Solved in f64
+/// with the cancellation free quadratic, `origin` sits a planet radius away from the center and a few meters above it.
+fn intersect_sphere(origin: Vector3<f32>, direction: Vector3<f32>, radius: f32) -> Option<(f32, f32)> {
+    let o = [origin.x() as f64, origin.y() as f64, origin.z() as f64];
+    let d = [direction.x() as f64, direction.y() as f64, direction.z() as f64];
+    let radius = radius as f64;
+
+    let b = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+    let length = (o[0] * o[0] + o[1] * o[1] + o[2] * o[2]).sqrt();
+    let c = (length - radius) * (length + radius);
+    let discriminant = b * b - c;
+
+    if 0.0 > discriminant {
+        return None;
+    }
+
+    let q = -(b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if 0.0 == q { (0.0, 0.0) } else { (q, c / q) };
+    let (near, far) = (t0.min(t1), t0.max(t1));
+    if 0.0 > far {
+        return None;
+    }
+
+    Some((near as f32, far as f32))
+}
+
+/// Exponential tone mapping of high dynamic range radiance.
+pub fn expose(c: Vector3<f32>, exposure: f32) -> TGAColor {
+    let quantize = |v: f32| ((1.0 - (-v * exposure).exp()).clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0 + 0.5) as u8;
+
+    [quantize(c.x()), quantize(c.y()), quantize(c.z()), 255].into()
+}
+
+/// Fills every pixel the depth buffer has not been written to with `sky` seen from `camera`.
+pub fn sky<I: Image>(image: &mut I, depth: &DepthBuffer, sky: &Sky, camera: &Camera, exposure: f32) {
+    fill_background(image, depth, camera, |direction| expose(sky.radiance(direction), exposure));
+}
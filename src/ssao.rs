@@ -0,0 +1,222 @@
+//! <https://learnopengl.com/Advanced-Lighting/SSAO>
+//!
+
+use crate::gl::embed;
+use crate::img::Buffer;
+use crate::img::DepthBuffer;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Inverse;
+use crate::la::Matrix;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::pbr::basis;
+use crate::rng::Rng;
+use crate::tga::TGAImage;
+
+const NOISE_SIZE: usize = 4;
+
+pub type OcclusionBuffer = Buffer<f32>;
+
+/// Screen space ambient occlusion, `radius` and `bias` are in view space units and `blur` is the box filter radius in pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct Ssao {
+    pub radius: f32,
+    pub samples: u32,
+    pub strength: f32,
+    pub bias: f32,
+    pub blur: u32,
+    pub seed: u32,
+}
+
+impl Default for Ssao {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            samples: 16,
+            strength: 1.0,
+            bias: 0.025,
+            blur: 2,
+            seed: 0,
+        }
+    }
+}
+
+impl Ssao {
+    /// Hemisphere samples around `+z`, denser near the origin.
+    pub fn kernel(&self) -> Vec<Vector3<f32>> {
+        let mut random = Rng::new(self.seed as u64);
+
+        (0..self.samples)
+            .map(|i| {
+                let sample: Vector3<f32> = [random.next_f32() * 2.0 - 1.0, random.next_f32() * 2.0 - 1.0, random.next_f32()].into();
+                let t = i as f32 / self.samples as f32;
+                sample.normal().scale(random.next_f32() * (0.1 + 0.9 * t * t))
+            })
+            .collect()
+    }
+
+    /// Occlusion factor per pixel, `1` is fully open. `normals` are in view space and are reconstructed from depth when absent.
+    pub fn compute(&self, depth: &DepthBuffer, normals: Option<&Buffer<Vector3<f32>>>, projection: &Matrix<f32, 4, 4>) -> OcclusionBuffer {
+        let width = depth.get_width();
+        let height = depth.get_height();
+
+        let mut occlusion = OcclusionBuffer::new(width, height, 1.0);
+
+        let Some(inverse) = projection.inverse() else {
+            return occlusion;
+        };
+
+        let mut positions = Buffer::<Option<Vector3<f32>>>::new(width, height, None);
+        for y in 0..height {
+            for x in 0..width {
+                let d = depth.get([x, y].into());
+                if 1.0 < d {
+                    continue;
+                }
+
+                let ndc: Vector4<f32> = [(x as f32 + 0.5) / width as f32 * 2.0 - 1.0, (y as f32 + 0.5) / height as f32 * 2.0 - 1.0, d * 2.0 - 1.0, 1.0].into();
+                let view = ndc * inverse;
+                positions.set([x, y].into(), Some(Vector3::from(view).scale(1.0 / view.w())));
+            }
+        }
+
+        let kernel = self.kernel();
+
+        let mut random = Rng::new(self.seed as u64 ^ 0x5EED);
+        let noise: Vec<Vector3<f32>> = (0..NOISE_SIZE * NOISE_SIZE).map(|_| Vector3::from([random.next_f32() * 2.0 - 1.0, random.next_f32() * 2.0 - 1.0, 0.0]).normal()).collect();
+
+        let position_at = |x: i32, y: i32| {
+            if 0 > x || 0 > y || x >= width as i32 || y >= height as i32 {
+                None
+            } else {
+                positions.get([x as u16, y as u16].into())
+            }
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let Some(p) = positions.get([x, y].into()) else {
+                    continue;
+                };
+
+                let n = match normals {
+                    Some(normals) => normals.get([x, y].into()).normal(),
+                    None => reconstruct_normal(p, |dx, dy| position_at(x as i32 + dx, y as i32 + dy)),
+                };
+
+                let rotation = noise[x as usize % NOISE_SIZE + y as usize % NOISE_SIZE * NOISE_SIZE];
+                let t = rotation - n.scale(rotation.dot(n));
+                // a rotation (nearly) parallel to the normal leaves no tangent, any basis around it will do
+                let (t, b) = if 1e-6 < t.dot(t) {
+                    let t = t.normal();
+                    (t, n.cross(t))
+                } else {
+                    basis(n)
+                };
+
+                let mut occluded = 0.0;
+
+                for &k in &kernel {
+                    let s = p + (t.scale(k.x()) + b.scale(k.y()) + n.scale(k.z())).scale(self.radius);
+
+                    let clip = embed(s, 1.0) * *projection;
+                    if 0.0 >= clip.w() {
+                        continue;
+                    }
+
+                    let sx = ((clip.x() / clip.w() + 1.0) * 0.5 * width as f32).floor() as i32;
+                    let sy = ((clip.y() / clip.w() + 1.0) * 0.5 * height as f32).floor() as i32;
+
+                    let Some(q) = position_at(sx, sy) else {
+                        continue;
+                    };
+
+                    if q.z() >= s.z() + self.bias {
+                        let range = (self.radius / (p.z() - q.z()).abs()).min(1.0);
+                        occluded += range * range * (3.0 - 2.0 * range);
+                    }
+                }
+
+                let open = 1.0 - occluded / kernel.len().max(1) as f32;
+                occlusion.set([x, y].into(), open.max(0.0).powf(self.strength));
+            }
+        }
+
+        if 0 < self.blur {
+            occlusion = blur(&occlusion, &positions, self.blur as i32);
+        }
+
+        occlusion
+    }
+}
+
+/// Grayscale view of an occlusion buffer.
+pub fn occlusion_image(occlusion: &OcclusionBuffer) -> TGAImage {
+    let mut image = TGAImage::new(occlusion.get_width(), occlusion.get_height());
+
+    for y in 0..occlusion.get_height() {
+        for x in 0..occlusion.get_width() {
+            let v = (occlusion.get([x, y].into()).clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+            image.set_color([x, y].into(), [v, v, v, 255].into());
+        }
+    }
+
+    image
+}
+
+fn reconstruct_normal(p: Vector3<f32>, mut position_at: impl FnMut(i32, i32) -> Option<Vector3<f32>>) -> Vector3<f32> {
+    let pick = |a: Option<Vector3<f32>>, b: Option<Vector3<f32>>| match (a, b) {
+        (Some(a), Some(b)) if (a.z() - p.z()).abs() < (b.z() - p.z()).abs() => a - p,
+        (_, Some(b)) => p - b,
+        (Some(a), None) => a - p,
+        (None, None) => Vector3::from(0.0),
+    };
+
+    let dx = pick(position_at(1, 0), position_at(-1, 0));
+    let dy = pick(position_at(0, 1), position_at(0, -1));
+
+    let n = dx.cross(dy);
+    if 0.0 == n.dot(n) {
+        [0.0, 0.0, 1.0].into()
+    } else {
+        n.normal()
+    }
+}
+
+fn blur(occlusion: &OcclusionBuffer, positions: &Buffer<Option<Vector3<f32>>>, radius: i32) -> OcclusionBuffer {
+    let width = occlusion.get_width() as i32;
+    let height = occlusion.get_height() as i32;
+
+    let mut blurred = occlusion.clone();
+
+    for y in 0..height {
+        for x in 0..width {
+            if positions.get([x as u16, y as u16].into()).is_none() {
+                continue;
+            }
+
+            let mut sum = 0.0;
+            let mut count = 0;
+
+            for sy in (y - radius).max(0)..=(y + radius).min(height - 1) {
+                for sx in (x - radius).max(0)..=(x + radius).min(width - 1) {
+                    if positions.get([sx as u16, sy as u16].into()).is_some() {
+                        sum += occlusion.get([sx as u16, sy as u16].into());
+                        count += 1;
+                    }
+                }
+            }
+
+            blurred.set([x as u16, y as u16].into(), sum / count as f32);
+        }
+    }
+
+    blurred
+}
@@ -24,6 +24,12 @@ pub const RED: [u8; 4] = [255u8, 0u8, 0u8, 255u8];
 pub const GREEN: [u8; 4] = [0u8, 255u8, 0u8, 255u8];
 pub const BLUE: [u8; 4] = [0u8, 0u8, 255u8, 255u8];
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
 pub const TGAHEADER_BYTES_COUNT: usize = 18;
 
 #[derive(Default)]
@@ -137,10 +143,10 @@ impl TGAImage {
                 image_height,
                 image_bits_per_pixel,
                 image_descriptor: match image_bits_per_pixel {
-                    16 => 0b00_00_0001,
-                    24 => 0b00_00_0000,
-                    32 => 0b00_00_1000,
-                    _ => 0b00_00_1000,
+                    16 => 0b0000_0001,
+                    24 => 0b0000_0000,
+                    32 => 0b0000_1000,
+                    _ => 0b0000_1000,
                 },
                 ..TGAHeader::default()
             },
@@ -160,7 +166,14 @@ impl TGAImage {
 
         file.read_exact(header_bytes)?;
 
-        let header = TGAHeader::from_bytes(header_bytes)?;
+        let mut header = TGAHeader::from_bytes(header_bytes)?;
+
+        let mut content = Vec::new();
+
+        file.read_to_end(&mut content)?;
+
+        let skip = header.id_length as usize + header.color_map_entries_count as usize * header.color_map_bits_per_entry as usize / 8;
+        let data = content.get(skip..).ok_or("图像数据解释失败")?;
 
         let mut bytes = vec![
             0;
@@ -170,7 +183,33 @@ impl TGAImage {
                 >> 3
         ];
 
-        file.read_exact(bytes.as_mut_slice())?;
+        match (header.image_type, header.image_bits_per_pixel) {
+            (2, 24) | (2, 32) | (3, 8) => {
+                let length = bytes.len();
+                bytes.copy_from_slice(data.get(..length).ok_or("图像数据解释失败")?);
+            }
+            (10, 24) | (10, 32) | (11, 8) => {
+                decode_rle(data, &mut bytes, header.image_bits_per_pixel as usize >> 3)?;
+                header.image_type -= 8;
+            }
+            _ => return Err("不支持的图像类型".into()),
+        }
+
+        header.id_length = 0;
+        header.color_map_type = 0;
+        header.color_map_origin = 0;
+        header.color_map_entries_count = 0;
+        header.color_map_bits_per_entry = 0;
+
+        if 0 != header.image_descriptor & 0b0010_0000 {
+            let stride = (header.image_width as usize * header.image_bits_per_pixel as usize) >> 3;
+            let height = header.image_height as usize;
+            for y in 0..height / 2 {
+                let (top, bottom) = bytes.split_at_mut((height - 1 - y) * stride);
+                top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+            }
+            header.image_descriptor &= !0b0010_0000;
+        }
 
         Ok(Self { header, bytes })
     }
@@ -199,13 +238,117 @@ impl TGAImage {
         self.header.image_height
     }
 
+    pub fn get_color(&self, coord: TGACoord) -> TGAColor {
+        let index = self.get_index(coord);
+        match self.header.image_bits_per_pixel {
+            8 => [self.bytes[index], self.bytes[index], self.bytes[index], 255].into(),
+            24 => [self.bytes[index + 2], self.bytes[index + 1], self.bytes[index], 255].into(),
+            _ => [self.bytes[index + 2], self.bytes[index + 1], self.bytes[index], self.bytes[index + 3]].into(),
+        }
+    }
+
     pub fn set_color(&mut self, coord: TGACoord, color: TGAColor) {
-        let index = ((coord.x() as u64 + coord.y() as u64 * self.header.image_width as u64)
-            * self.header.image_bits_per_pixel as u64) as usize
-            >> 3;
-        self.bytes[index + 0] = color.b();
-        self.bytes[index + 1] = color.g();
-        self.bytes[index + 2] = color.r();
-        self.bytes[index + 3] = color.a();
+        let index = self.get_index(coord);
+        match self.header.image_bits_per_pixel {
+            8 => {
+                self.bytes[index] = ((color.r() as u32 * 77 + color.g() as u32 * 150 + color.b() as u32 * 29) >> 8) as u8;
+            }
+            24 => {
+                self.bytes[index] = color.b();
+                self.bytes[index + 1] = color.g();
+                self.bytes[index + 2] = color.r();
+            }
+            _ => {
+                self.bytes[index] = color.b();
+                self.bytes[index + 1] = color.g();
+                self.bytes[index + 2] = color.r();
+                self.bytes[index + 3] = color.a();
+            }
+        }
+    }
+
+    /// Replaces the region connected to `seed` whose channels all differ from the seed color by at most `tolerance`, returns the number of filled pixels.
+    pub fn flood_fill(&mut self, seed: TGACoord, color: TGAColor, connectivity: Connectivity, tolerance: u8) -> usize {
+        let width = self.header.image_width as i32;
+        let height = self.header.image_height as i32;
+
+        if seed.x() as i32 >= width || seed.y() as i32 >= height {
+            return 0;
+        }
+
+        let target = self.get_color(seed);
+        let matches = |c: TGAColor| (0..4).all(|i| c[0][i].abs_diff(target[0][i]) <= tolerance);
+
+        let neighbours: &[(i32, i32)] = match connectivity {
+            Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+            Connectivity::Eight => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, 1), (1, -1), (-1, -1)],
+        };
+
+        let mut visited = vec![false; width as usize * height as usize];
+        let mut stack = vec![(seed.x() as i32, seed.y() as i32)];
+        visited[seed.x() as usize + seed.y() as usize * width as usize] = true;
+
+        let mut count = 0;
+
+        while let Some((x, y)) = stack.pop() {
+            let coord: TGACoord = [x as u16, y as u16].into();
+            self.set_color(coord, color);
+            count += 1;
+
+            for &(dx, dy) in neighbours {
+                let (nx, ny) = (x + dx, y + dy);
+                if 0 > nx || nx >= width || 0 > ny || ny >= height {
+                    continue;
+                }
+
+                let i = nx as usize + ny as usize * width as usize;
+                if visited[i] {
+                    continue;
+                }
+
+                if matches(self.get_color([nx as u16, ny as u16].into())) {
+                    visited[i] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        count
+    }
+
+    fn get_index(&self, coord: TGACoord) -> usize {
+        ((coord.x() as u64 + coord.y() as u64 * self.header.image_width as u64) * self.header.image_bits_per_pixel as u64) as usize >> 3
+    }
+}
+
+fn decode_rle(encoded: &[u8], bytes: &mut [u8], pixel_bytes: usize) -> Result<(), Box<dyn Error>> {
+    let mut i = 0;
+    let mut o = 0;
+
+    while o < bytes.len() {
+        let packet = *encoded.get(i).ok_or("RLE 数据解释失败")?;
+        i += 1;
+
+        let count = (packet & 0x7F) as usize + 1;
+        let length = count * pixel_bytes;
+
+        if o + length > bytes.len() {
+            return Err("RLE 数据解释失败".into());
+        }
+
+        if 0 != packet & 0x80 {
+            let pixel = encoded.get(i..i + pixel_bytes).ok_or("RLE 数据解释失败")?;
+            for chunk in bytes[o..o + length].chunks_exact_mut(pixel_bytes) {
+                chunk.copy_from_slice(pixel);
+            }
+            i += pixel_bytes;
+        } else {
+            bytes[o..o + length].copy_from_slice(encoded.get(i..i + length).ok_or("RLE 数据解释失败")?);
+            i += length;
+        }
+
+        o += length;
     }
+
+    Ok(())
 }
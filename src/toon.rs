@@ -0,0 +1,222 @@
+//! <https://en.wikipedia.org/wiki/Cel_shading>
+//! <https://www.cs.princeton.edu/courses/archive/fall02/cs526/papers/raskar01.pdf>
+//!
+
+use crate::gl::embed;
+use crate::gl::line;
+use crate::gl::CullMode;
+use crate::gl::Fragment;
+use crate::gl::LineStyle;
+use crate::gl::RenderState;
+use crate::gl::Shader;
+use crate::img::sample;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Dot;
+use crate::la::Hadamard;
+use crate::la::Normal;
+use crate::la::Scale;
+use crate::la::Vector2;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::obj::Obj;
+use crate::shader::interpolate;
+use crate::shader::to_rgba;
+use crate::shader::transform_normal;
+use crate::shader::transform_point;
+use crate::shader::Material;
+use crate::shader::Uniforms;
+use crate::tga::TGAColor;
+use crate::tga::TGAImage;
+
+/// Cel shading, the diffuse term is quantized into `bands` levels or looked up in `ramp` along `u`, the specular term is a hard highlight above `specular_threshold`.
+pub struct ToonShader<'a> {
+    pub obj: &'a Obj,
+    pub uniforms: Uniforms,
+    pub material: Material,
+    pub bands: u32,
+    pub ramp: Option<&'a TGAImage>,
+    pub specular_threshold: f32,
+    varying_position: [Vector3<f32>; 3],
+    varying_normal: [Vector3<f32>; 3],
+}
+
+impl<'a> ToonShader<'a> {
+    pub fn new(obj: &'a Obj, uniforms: Uniforms, material: Material, bands: u32) -> Self {
+        Self {
+            obj,
+            uniforms,
+            material,
+            bands,
+            ramp: None,
+            specular_threshold: 0.5,
+            varying_position: [0.0.into(); 3],
+            varying_normal: [0.0.into(); 3],
+        }
+    }
+
+    pub fn quantize(&self, intensity: f32) -> f32 {
+        let intensity = intensity.clamp(0.0, 1.0);
+
+        match self.ramp {
+            Some(ramp) => sample(ramp, [intensity, 0.5].into()).x(),
+            None => {
+                let bands = self.bands.max(1) as f32;
+                (intensity * bands).ceil() / bands
+            }
+        }
+    }
+}
+
+impl Shader for ToonShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let position = transform_point(self.obj.vert(face, nthvert), &self.uniforms.model);
+
+        self.varying_position[nthvert] = position;
+        self.varying_normal[nthvert] = transform_normal(self.obj.normal(face, nthvert), &self.uniforms.normals);
+
+        embed(position, 1.0) * self.uniforms.view_projection
+    }
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor> {
+        let bar = fragment.bar;
+
+        let position = interpolate(&self.varying_position, bar);
+        let mut normal = interpolate(&self.varying_normal, bar).normal();
+        if !fragment.front_facing {
+            normal = normal.scale(-1.0);
+        }
+
+        let v = (self.uniforms.eye - position).normal();
+        let mut color = self.material.ambient.scale(self.uniforms.ambient_occlusion(fragment));
+
+        for (i, light) in self.uniforms.lights.iter().enumerate() {
+            if 0.5 > self.uniforms.visibility(i, position, normal) {
+                continue;
+            }
+
+            light.illuminate(position, |sample| {
+                let diffuse = normal.dot(sample.direction);
+                if 0.0 >= diffuse {
+                    return;
+                }
+
+                let highlight = normal.dot((sample.direction + v).normal()).max(0.0).powf(self.material.shininess);
+                let specular = if self.specular_threshold < highlight { self.material.specular } else { 0.0.into() };
+
+                color += sample.radiance.hadamard(self.material.diffuse.scale(self.quantize(diffuse)) + specular);
+            });
+        }
+
+        let color = self.uniforms.fog(color, position);
+
+        Some(to_rgba(color, self.material.opacity))
+    }
+}
+
+/// Feature lines drawn on top of a shaded render, silhouettes between front and back facing neighbours, creases whose dihedral angle exceeds `crease_angle` radians and open boundaries.
+#[derive(Clone, Copy, Debug)]
+pub struct Outline {
+    pub style: LineStyle,
+    pub crease_angle: f32,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            style: LineStyle {
+                width: 2.0,
+                depth_bias: 2e-3,
+                ..LineStyle::default()
+            },
+            crease_angle: 1.0,
+        }
+    }
+}
+
+impl Outline {
+    /// World space end points of the feature edges of `obj` seen from `uniforms.eye`, `adjacency` comes from `Obj::adjacency`, degenerate faces are skipped.
+    pub fn edges(&self, obj: &Obj, adjacency: &[[Option<usize>; 3]], uniforms: &Uniforms) -> Vec<[Vector3<f32>; 2]> {
+        let normals: Vec<Vector3<f32>> = (0..obj.fs.len()).map(|face| transform_normal(obj.face_normal(face), &uniforms.normals).normal()).collect();
+        let facing: Vec<bool> = (0..obj.fs.len()).map(|face| 0.0 < normals[face].dot(uniforms.eye - transform_point(obj.vert(face, 0), &uniforms.model))).collect();
+        let cos = self.crease_angle.cos();
+
+        let mut edges = Vec::new();
+
+        for (face, neighbours) in adjacency.iter().enumerate() {
+            if !normals[face].x().is_finite() {
+                continue;
+            }
+
+            for (nthvert, neighbour) in neighbours.iter().enumerate() {
+                let feature = match *neighbour {
+                    Some(other) if face < other => facing[face] != facing[other] || cos > normals[face].dot(normals[other]),
+                    Some(_) => false,
+                    None => true,
+                };
+
+                if feature {
+                    edges.push([obj.vert(face, nthvert), obj.vert(face, (nthvert + 1) % 3)].map(|p| transform_point(p, &uniforms.model)));
+                }
+            }
+        }
+
+        edges
+    }
+
+    /// Depth tested against `depth` from the shaded pass so hidden edges stay hidden.
+    pub fn draw<I: Image>(&self, image: &mut I, depth: &DepthBuffer, obj: &Obj, adjacency: &[[Option<usize>; 3]], uniforms: &Uniforms) {
+        for edge in self.edges(obj, adjacency, uniforms) {
+            let clip = edge.map(|p| embed(p, 1.0) * uniforms.view_projection);
+            line(&clip, image, Some(depth), &self.style);
+        }
+    }
+}
+
+/// Inverted hull outline, back faces pushed out along their normals by `width` pixels and filled with `color`, draw with `HullShader::state`.
+pub struct HullShader<'a> {
+    pub obj: &'a Obj,
+    pub uniforms: Uniforms,
+    pub width: f32,
+    pub color: TGAColor,
+    pub viewport: Vector2<f32>,
+}
+
+impl<'a> HullShader<'a> {
+    pub fn new(obj: &'a Obj, uniforms: Uniforms, width: f32, color: TGAColor, viewport: Vector2<f32>) -> Self {
+        Self { obj, uniforms, width, color, viewport }
+    }
+
+    pub fn state() -> RenderState {
+        RenderState {
+            cull: CullMode::Front,
+            ..RenderState::default()
+        }
+    }
+}
+
+impl Shader for HullShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let position = transform_point(self.obj.vert(face, nthvert), &self.uniforms.model);
+        let normal = transform_normal(self.obj.normal(face, nthvert), &self.uniforms.normals);
+
+        let clip = embed(position, 1.0) * self.uniforms.view_projection;
+        let direction = embed(normal, 0.0) * self.uniforms.view_projection;
+
+        let offset = Vector2::<f32>::from([direction.x() * self.viewport.x(), direction.y() * self.viewport.y()]);
+        let length = offset.dot(offset).sqrt();
+        if 1e-12 > length {
+            return clip;
+        }
+
+        let scale = 2.0 * self.width * clip.w() / length;
+        clip + Vector4::from([offset.x() * scale / self.viewport.x(), offset.y() * scale / self.viewport.y(), 0.0, 0.0])
+    }
+
+    fn fragment(&mut self, _: &Fragment) -> Option<TGAColor> {
+        Some(self.color)
+    }
+}
@@ -0,0 +1,526 @@
+//! <https://learn.microsoft.com/en-us/typography/opentype/spec/otff>
+//!
+//!
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::path::Path as FilePath;
+
+use crate::cg::fill_path;
+use crate::cg::FillRule;
+use crate::cg::Path;
+use crate::img::Image;
+use crate::la::Vector2;
+use crate::la::X;
+use crate::la::Y;
+use crate::tga::TGAColor;
+
+#[derive(Clone, Copy, Debug)]
+struct OutlinePoint {
+    x: f32,
+    y: f32,
+    on_curve: bool,
+}
+
+pub struct TrueTypeFont {
+    pub units_per_em: u16,
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    bytes: Vec<u8>,
+    glyf: usize,
+    locations: Vec<usize>,
+    h_metrics: Vec<(u16, i16)>,
+    cmap: HashMap<u32, u16>,
+    kerning: HashMap<(u16, u16), i16>,
+}
+
+impl TrueTypeFont {
+    pub fn load<P: AsRef<FilePath>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let mut file = OpenOptions::new().read(true).open(path)?;
+
+        let mut bytes = Vec::new();
+
+        file.read_to_end(&mut bytes)?;
+
+        Self::from_bytes(bytes)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Box<dyn Error>> {
+        let num_tables = read_u16(&bytes, 4)? as usize;
+
+        let mut tables: HashMap<[u8; 4], usize> = HashMap::new();
+        for i in 0..num_tables {
+            let record = 12 + i * 16;
+            let tag: [u8; 4] = bytes.get(record..record + 4).ok_or("表目录解释失败")?.try_into()?;
+            tables.insert(tag, read_u32(&bytes, record + 8)? as usize);
+        }
+
+        let table = |tag: &[u8; 4]| tables.get(tag).copied().ok_or_else(|| format!("缺少 {} 表", String::from_utf8_lossy(tag)));
+
+        let head = table(b"head")?;
+        let units_per_em = read_u16(&bytes, head + 18)?;
+        let long_locations = 0 != read_i16(&bytes, head + 50)?;
+
+        let num_glyphs = read_u16(&bytes, table(b"maxp")? + 4)? as usize;
+
+        let hhea = table(b"hhea")?;
+        let ascender = read_i16(&bytes, hhea + 4)?;
+        let descender = read_i16(&bytes, hhea + 6)?;
+        let line_gap = read_i16(&bytes, hhea + 8)?;
+        let num_h_metrics = read_u16(&bytes, hhea + 34)? as usize;
+
+        let hmtx = table(b"hmtx")?;
+        let mut h_metrics = Vec::with_capacity(num_glyphs);
+        for i in 0..num_glyphs {
+            if i < num_h_metrics {
+                h_metrics.push((read_u16(&bytes, hmtx + i * 4)?, read_i16(&bytes, hmtx + i * 4 + 2)?));
+            } else {
+                let advance = h_metrics.last().map(|m: &(u16, i16)| m.0).unwrap_or(0);
+                h_metrics.push((advance, read_i16(&bytes, hmtx + num_h_metrics * 4 + (i - num_h_metrics) * 2)?));
+            }
+        }
+
+        let loca = table(b"loca")?;
+        let mut locations = Vec::with_capacity(num_glyphs + 1);
+        for i in 0..=num_glyphs {
+            locations.push(if long_locations { read_u32(&bytes, loca + i * 4)? as usize } else { read_u16(&bytes, loca + i * 2)? as usize * 2 });
+        }
+
+        let glyf = table(b"glyf")?;
+
+        let cmap = parse_cmap(&bytes, table(b"cmap")?)?;
+
+        let kerning = match tables.get(b"kern") {
+            Some(&kern) => parse_kern(&bytes, kern)?,
+            None => HashMap::new(),
+        };
+
+        Ok(Self {
+            units_per_em,
+            ascender,
+            descender,
+            line_gap,
+            bytes,
+            glyf,
+            locations,
+            h_metrics,
+            cmap,
+            kerning,
+        })
+    }
+
+    pub fn glyph_index(&self, c: char) -> u16 {
+        self.cmap.get(&(c as u32)).copied().unwrap_or(0)
+    }
+
+    pub fn advance_width(&self, glyph: u16) -> u16 {
+        self.h_metrics.get(glyph as usize).map(|m| m.0).unwrap_or(0)
+    }
+
+    pub fn left_side_bearing(&self, glyph: u16) -> i16 {
+        self.h_metrics.get(glyph as usize).map(|m| m.1).unwrap_or(0)
+    }
+
+    pub fn kerning(&self, left: u16, right: u16) -> i16 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0)
+    }
+
+    /// Outline of `glyph` in font units scaled by `scale` and moved to `origin`, y grows upwards.
+    pub fn glyph_path(&self, glyph: u16, origin: Vector2<f32>, scale: f32) -> Result<Path, Box<dyn Error>> {
+        let mut path = Path::new();
+
+        for contour in self.glyph_contours(glyph, 0)? {
+            let points: Vec<OutlinePoint> = contour
+                .iter()
+                .map(|p| OutlinePoint {
+                    x: origin.x() + p.x * scale,
+                    y: origin.y() + p.y * scale,
+                    on_curve: p.on_curve,
+                })
+                .collect();
+
+            append_contour(&mut path, &points);
+        }
+
+        Ok(path)
+    }
+
+    /// Lays out `text` left to right with kerning, `size` is the em size in pixels and `pos` the start of the first baseline.
+    pub fn text_path(&self, text: &str, pos: Vector2<f32>, size: f32) -> Result<Path, Box<dyn Error>> {
+        let scale = size / self.units_per_em as f32;
+        let line_height = (self.ascender as f32 - self.descender as f32 + self.line_gap as f32) * scale;
+
+        let mut path = Path::new();
+
+        let mut pen_x = pos.x();
+        let mut baseline = pos.y();
+        let mut previous: Option<u16> = None;
+
+        for c in text.chars() {
+            if '\n' == c {
+                pen_x = pos.x();
+                baseline -= line_height;
+                previous = None;
+                continue;
+            }
+
+            let glyph = self.glyph_index(c);
+
+            if let Some(previous) = previous {
+                pen_x += self.kerning(previous, glyph) as f32 * scale;
+            }
+
+            path.append(&self.glyph_path(glyph, [pen_x, baseline].into(), scale)?);
+
+            pen_x += self.advance_width(glyph) as f32 * scale;
+            previous = Some(glyph);
+        }
+
+        Ok(path)
+    }
+
+    fn glyph_contours(&self, glyph: u16, depth: usize) -> Result<Vec<Vec<OutlinePoint>>, Box<dyn Error>> {
+        if 8 < depth {
+            return Err("复合字形嵌套过深".into());
+        }
+
+        let index = glyph as usize;
+        if index + 1 >= self.locations.len() {
+            return Err("字形索引越界".into());
+        }
+
+        let start = self.glyf + self.locations[index];
+        let end = self.glyf + self.locations[index + 1];
+
+        if start == end {
+            return Ok(Vec::new());
+        }
+
+        let bytes = &self.bytes;
+        let num_contours = read_i16(bytes, start)?;
+
+        if 0 > num_contours {
+            return self.composite_contours(start + 10, depth);
+        }
+
+        let num_contours = num_contours as usize;
+
+        let mut end_points = Vec::with_capacity(num_contours);
+        for i in 0..num_contours {
+            end_points.push(read_u16(bytes, start + 10 + i * 2)? as usize);
+        }
+
+        let num_points = end_points.last().map(|&e| e + 1).unwrap_or(0);
+
+        let instructions_length = read_u16(bytes, start + 10 + num_contours * 2)? as usize;
+        let mut offset = start + 12 + num_contours * 2 + instructions_length;
+
+        let mut flags = Vec::with_capacity(num_points);
+        while flags.len() < num_points {
+            let flag = read_u8(bytes, offset)?;
+            offset += 1;
+            flags.push(flag);
+
+            if 0 != flag & 0x08 {
+                let repeat = read_u8(bytes, offset)?;
+                offset += 1;
+                for _ in 0..repeat {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(num_points);
+
+        let mut xs = Vec::with_capacity(num_points);
+        let mut value = 0i32;
+        for &flag in flags.iter() {
+            if 0 != flag & 0x02 {
+                let delta = read_u8(bytes, offset)? as i32;
+                offset += 1;
+                value += if 0 != flag & 0x10 { delta } else { -delta };
+            } else if 0 == flag & 0x10 {
+                value += read_i16(bytes, offset)? as i32;
+                offset += 2;
+            }
+            xs.push(value);
+        }
+
+        let mut ys = Vec::with_capacity(num_points);
+        let mut value = 0i32;
+        for &flag in flags.iter() {
+            if 0 != flag & 0x04 {
+                let delta = read_u8(bytes, offset)? as i32;
+                offset += 1;
+                value += if 0 != flag & 0x20 { delta } else { -delta };
+            } else if 0 == flag & 0x20 {
+                value += read_i16(bytes, offset)? as i32;
+                offset += 2;
+            }
+            ys.push(value);
+        }
+
+        let mut contours = Vec::with_capacity(num_contours);
+        let mut first = 0;
+        for &last in end_points.iter() {
+            if last < first || last >= num_points {
+                return Err("字形轮廓解释失败".into());
+            }
+
+            contours.push(
+                (first..=last)
+                    .map(|i| OutlinePoint {
+                        x: xs[i] as f32,
+                        y: ys[i] as f32,
+                        on_curve: 0 != flags[i] & 0x01,
+                    })
+                    .collect(),
+            );
+            first = last + 1;
+        }
+
+        Ok(contours)
+    }
+
+    fn composite_contours(&self, mut offset: usize, depth: usize) -> Result<Vec<Vec<OutlinePoint>>, Box<dyn Error>> {
+        let bytes = &self.bytes;
+
+        let mut contours = Vec::new();
+
+        loop {
+            let flags = read_u16(bytes, offset)?;
+            let component = read_u16(bytes, offset + 2)?;
+            offset += 4;
+
+            let (dx, dy) = if 0 != flags & 0x0001 {
+                offset += 4;
+                (read_i16(bytes, offset - 4)? as f32, read_i16(bytes, offset - 2)? as f32)
+            } else {
+                offset += 2;
+                (read_u8(bytes, offset - 2)? as i8 as f32, read_u8(bytes, offset - 1)? as i8 as f32)
+            };
+
+            let f2dot14 = |offset: usize| -> Result<f32, Box<dyn Error>> { Ok(read_i16(bytes, offset)? as f32 / 16384.0) };
+
+            let (a, b, c, d) = if 0 != flags & 0x0008 {
+                offset += 2;
+                let s = f2dot14(offset - 2)?;
+                (s, 0.0, 0.0, s)
+            } else if 0 != flags & 0x0040 {
+                offset += 4;
+                (f2dot14(offset - 4)?, 0.0, 0.0, f2dot14(offset - 2)?)
+            } else if 0 != flags & 0x0080 {
+                offset += 8;
+                (f2dot14(offset - 8)?, f2dot14(offset - 6)?, f2dot14(offset - 4)?, f2dot14(offset - 2)?)
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+
+            // ARGS_ARE_XY_VALUES is required, point matching offsets are treated as zero.
+            let (dx, dy) = if 0 != flags & 0x0002 { (dx, dy) } else { (0.0, 0.0) };
+
+            for contour in self.glyph_contours(component, depth + 1)? {
+                contours.push(
+                    contour
+                        .into_iter()
+                        .map(|p| OutlinePoint {
+                            x: p.x * a + p.y * c + dx,
+                            y: p.x * b + p.y * d + dy,
+                            on_curve: p.on_curve,
+                        })
+                        .collect(),
+                );
+            }
+
+            if 0 == flags & 0x0020 {
+                break;
+            }
+        }
+
+        Ok(contours)
+    }
+}
+
+/// Fills `text` laid out by `TrueTypeFont::text_path` with anti-aliased edges.
+pub fn draw_text<I: Image>(image: &mut I, font: &TrueTypeFont, pos: Vector2<f32>, text: &str, size: f32, color: TGAColor) -> Result<(), Box<dyn Error>> {
+    let path = font.text_path(text, pos, size)?;
+
+    let clip = (0, 0, image.get_width() as i32, image.get_height() as i32);
+    fill_path(&path, FillRule::NonZero, clip, |x, y, coverage| image.blend_pixel(x, y, color, coverage));
+
+    Ok(())
+}
+
+fn append_contour(path: &mut Path, points: &[OutlinePoint]) {
+    if points.is_empty() {
+        return;
+    }
+
+    let midpoint = |a: &OutlinePoint, b: &OutlinePoint| -> Vector2<f32> { [(a.x + b.x) * 0.5, (a.y + b.y) * 0.5].into() };
+    let point = |p: &OutlinePoint| -> Vector2<f32> { [p.x, p.y].into() };
+
+    let count = points.len();
+
+    let (start, skip) = match points.iter().position(|p| p.on_curve) {
+        Some(i) => (point(&points[i]), i + 1),
+        None => (midpoint(&points[count - 1], &points[0]), 0),
+    };
+
+    path.move_to(start);
+
+    let mut control: Option<&OutlinePoint> = None;
+
+    for p in points.iter().cycle().skip(skip).take(count - skip.min(1)) {
+        match (control, p.on_curve) {
+            (None, true) => path.line_to(point(p)),
+            (None, false) => control = Some(p),
+            (Some(c), true) => {
+                path.quad_to(point(c), point(p));
+                control = None;
+            }
+            (Some(c), false) => {
+                path.quad_to(point(c), midpoint(c, p));
+                control = Some(p);
+            }
+        }
+    }
+
+    match control {
+        Some(c) => path.quad_to(point(c), start),
+        None => path.line_to(start),
+    }
+
+    path.close();
+}
+
+fn parse_cmap(bytes: &[u8], cmap: usize) -> Result<HashMap<u32, u16>, Box<dyn Error>> {
+    let num_tables = read_u16(bytes, cmap + 2)? as usize;
+
+    let mut best: Option<(u32, usize)> = None;
+    for i in 0..num_tables {
+        let record = cmap + 4 + i * 8;
+        let platform = read_u16(bytes, record)?;
+        let encoding = read_u16(bytes, record + 2)?;
+        let offset = cmap + read_u32(bytes, record + 4)? as usize;
+        let format = read_u16(bytes, offset)?;
+
+        let rank = match (platform, encoding, format) {
+            (3, 10, 12) | (0, 4, 12) | (0, 6, 12) => 3,
+            (3, 1, 4) | (0, _, 4) => 2,
+            (_, _, 4) | (_, _, 12) => 1,
+            _ => 0,
+        };
+
+        if 0 < rank && best.is_none_or(|(r, _)| r < rank) {
+            best = Some((rank, offset));
+        }
+    }
+
+    let Some((_, offset)) = best else {
+        return Err("不支持的 cmap 子表".into());
+    };
+
+    let mut map = HashMap::new();
+
+    match read_u16(bytes, offset)? {
+        4 => {
+            let seg_count = read_u16(bytes, offset + 6)? as usize / 2;
+            let end_codes = offset + 14;
+            let start_codes = end_codes + seg_count * 2 + 2;
+            let id_deltas = start_codes + seg_count * 2;
+            let id_range_offsets = id_deltas + seg_count * 2;
+
+            for i in 0..seg_count {
+                let end = read_u16(bytes, end_codes + i * 2)?;
+                let start = read_u16(bytes, start_codes + i * 2)?;
+                let delta = read_u16(bytes, id_deltas + i * 2)?;
+                let range_offset = read_u16(bytes, id_range_offsets + i * 2)? as usize;
+
+                if start > end {
+                    continue;
+                }
+
+                for code in start..=end {
+                    let glyph = if 0 == range_offset {
+                        code.wrapping_add(delta)
+                    } else {
+                        let address = id_range_offsets + i * 2 + range_offset + (code - start) as usize * 2;
+                        match read_u16(bytes, address)? {
+                            0 => 0,
+                            glyph => glyph.wrapping_add(delta),
+                        }
+                    };
+
+                    if 0 != glyph && 0xFFFF != code {
+                        map.insert(code as u32, glyph);
+                    }
+                }
+            }
+        }
+        12 => {
+            let num_groups = read_u32(bytes, offset + 12)? as usize;
+
+            for i in 0..num_groups {
+                let group = offset + 16 + i * 12;
+                let start = read_u32(bytes, group)?;
+                let end = read_u32(bytes, group + 4)?;
+                let glyph = read_u32(bytes, group + 8)?;
+
+                for code in start..=end.min(0x10FFFF) {
+                    let index = glyph.checked_add(code - start).and_then(|index| u16::try_from(index).ok()).ok_or("cmap 字形索引越界")?;
+                    map.insert(code, index);
+                }
+            }
+        }
+        _ => return Err("不支持的 cmap 子表".into()),
+    }
+
+    Ok(map)
+}
+
+fn parse_kern(bytes: &[u8], kern: usize) -> Result<HashMap<(u16, u16), i16>, Box<dyn Error>> {
+    let mut kerning = HashMap::new();
+
+    if 0 != read_u16(bytes, kern)? {
+        return Ok(kerning);
+    }
+
+    let num_tables = read_u16(bytes, kern + 2)? as usize;
+    let mut offset = kern + 4;
+
+    for _ in 0..num_tables {
+        let length = read_u16(bytes, offset + 2)? as usize;
+        let coverage = read_u16(bytes, offset + 4)?;
+
+        if 0 == coverage >> 8 && 0 != coverage & 0x0001 && 0 == coverage & 0x0004 {
+            let num_pairs = read_u16(bytes, offset + 6)? as usize;
+            for i in 0..num_pairs {
+                let pair = offset + 14 + i * 6;
+                let entry = kerning.entry((read_u16(bytes, pair)?, read_u16(bytes, pair + 2)?)).or_insert(0);
+                *entry = entry.checked_add(read_i16(bytes, pair + 4)?).ok_or("kern 数据溢出")?;
+            }
+        }
+
+        offset += length;
+    }
+
+    Ok(kerning)
+}
+
+fn read_u8(bytes: &[u8], offset: usize) -> Result<u8, Box<dyn Error>> {
+    Ok(*bytes.get(offset).ok_or("字体数据越界")?)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, Box<dyn Error>> {
+    Ok(u16::from_be_bytes(bytes.get(offset..offset + 2).ok_or("字体数据越界")?.try_into()?))
+}
+
+fn read_i16(bytes: &[u8], offset: usize) -> Result<i16, Box<dyn Error>> {
+    Ok(i16::from_be_bytes(bytes.get(offset..offset + 2).ok_or("字体数据越界")?.try_into()?))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Box<dyn Error>> {
+    Ok(u32::from_be_bytes(bytes.get(offset..offset + 4).ok_or("字体数据越界")?.try_into()?))
+}
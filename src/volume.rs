@@ -0,0 +1,146 @@
+//! <https://en.wikipedia.org/wiki/Shadow_volume>
+//! <https://developer.nvidia.com/gpugems/gpugems/part-i-natural-effects/chapter-9-efficient-shadow-volume-rendering>
+//!
+
+use crate::gl::stencil_triangle;
+use crate::gl::Blend;
+use crate::gl::Compare;
+use crate::gl::CullMode;
+use crate::gl::Fragment;
+use crate::gl::RenderState;
+use crate::gl::Shader;
+use crate::gl::StencilFace;
+use crate::gl::StencilOp;
+use crate::gl::StencilState;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::img::StencilBuffer;
+use crate::la::Cross;
+use crate::la::Dot;
+use crate::la::Matrix;
+use crate::la::Scale;
+use crate::la::Vector3;
+use crate::la::Vector4;
+use crate::la::X;
+use crate::la::Y;
+use crate::la::Z;
+use crate::light::Light;
+use crate::obj::Obj;
+use crate::shader::transform_point;
+use crate::tga::TGAColor;
+
+/// Closed shadow volume in world space, side and back cap vertices lie at infinity (`w = 0`), so it must be drawn with `infinite_perspective`.
+#[derive(Clone, Debug)]
+pub struct ShadowVolume {
+    pub triangles: Vec<[Vector4<f32>; 3]>,
+}
+
+impl ShadowVolume {
+    /// Extrudes the silhouette of `obj` away from `light`, `adjacency` comes from `Obj::adjacency` and open edges of lit faces count as silhouette edges.
+    pub fn new(obj: &Obj, adjacency: &[[Option<usize>; 3]], model: &Matrix<f32, 4, 4>, light: &Light) -> Self {
+        let positions: Vec<[Vector3<f32>; 3]> = (0..obj.fs.len()).map(|face| [0, 1, 2].map(|nthvert| transform_point(obj.vert(face, nthvert), model))).collect();
+
+        let extrude = |p: Vector3<f32>| -> Vector4<f32> {
+            let d = match *light {
+                Light::Directional { direction, .. } => direction,
+                Light::Point { position, .. } | Light::Spot { position, .. } | Light::Area { position, .. } => p - position,
+            };
+            [d.x(), d.y(), d.z(), 0.0].into()
+        };
+        let finite = |p: Vector3<f32>| -> Vector4<f32> { [p.x(), p.y(), p.z(), 1.0].into() };
+
+        let lit: Vec<bool> = positions
+            .iter()
+            .map(|&[a, b, c]| {
+                let n = (b - a).cross(c - a);
+                0.0 > n.dot(Vector3::from(extrude((a + b + c).scale(1.0 / 3.0))))
+            })
+            .collect();
+
+        let mut triangles = Vec::new();
+
+        for (face, &[a, b, c]) in positions.iter().enumerate() {
+            if !lit[face] {
+                continue;
+            }
+
+            triangles.push([finite(a), finite(b), finite(c)]);
+            triangles.push([extrude(a), extrude(c), extrude(b)]);
+
+            for nthvert in 0..3 {
+                let silhouette = match adjacency[face][nthvert] {
+                    Some(neighbour) => !lit[neighbour],
+                    None => true,
+                };
+
+                if silhouette {
+                    let p = positions[face][nthvert];
+                    let q = positions[face][(nthvert + 1) % 3];
+
+                    triangles.push([finite(p), extrude(q), finite(q)]);
+                    triangles.push([finite(p), extrude(p), extrude(q)]);
+                }
+            }
+        }
+
+        Self { triangles }
+    }
+
+    /// Depth-fail (Carmack's reverse) counting into `stencil` against the depth of an already drawn scene, non zero stencil values are in shadow.
+    pub fn draw<I: Image>(&self, image: &mut I, depth: &mut DepthBuffer, stencil: &mut StencilBuffer, view_projection: &Matrix<f32, 4, 4>) {
+        let state = RenderState {
+            cull: CullMode::None,
+            depth_test: Compare::Less,
+            depth_write: false,
+            color_write: false,
+            blend: None,
+            stencil: Some(StencilState {
+                front: StencilFace {
+                    depth_fail: StencilOp::DecrementWrap,
+                    ..StencilFace::default()
+                },
+                back: StencilFace {
+                    depth_fail: StencilOp::IncrementWrap,
+                    ..StencilFace::default()
+                },
+                ..StencilState::default()
+            }),
+        };
+
+        let mut shader = VolumeShader;
+
+        for triangle in &self.triangles {
+            let clip = triangle.map(|v| v * *view_projection);
+            stencil_triangle(&clip, &mut shader, image, depth, Some(stencil), &state);
+        }
+    }
+
+    /// Additive pass over the surfaces drawn before whose stencil value is zero, for adding the light outside the volumes.
+    pub fn lit_state() -> RenderState {
+        RenderState {
+            depth_test: Compare::LessEqual,
+            depth_write: false,
+            blend: Some(Blend::additive()),
+            stencil: Some(StencilState::new(
+                StencilFace {
+                    compare: Compare::Equal,
+                    ..StencilFace::default()
+                },
+                0,
+            )),
+            ..RenderState::default()
+        }
+    }
+}
+
+struct VolumeShader;
+
+impl Shader for VolumeShader {
+    fn vertex(&mut self, _face: usize, _nthvert: usize) -> Vector4<f32> {
+        0.0.into()
+    }
+
+    fn fragment(&mut self, _fragment: &Fragment) -> Option<TGAColor> {
+        Some([0, 0, 0, 0].into())
+    }
+}
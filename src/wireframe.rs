@@ -0,0 +1,174 @@
+//! <https://developer.download.nvidia.com/SDK/10/direct3d/Source/SolidWireframe/Doc/SolidWireframe.pdf>
+//! <https://registry.khronos.org/OpenGL-Refpages/gl4/html/glPolygonOffset.xhtml>
+//!
+
+use std::collections::HashSet;
+
+use crate::gl::draw;
+use crate::gl::embed;
+use crate::gl::line;
+use crate::gl::Fragment;
+use crate::gl::LineStyle;
+use crate::gl::RenderState;
+use crate::gl::Shader;
+use crate::img::DepthBuffer;
+use crate::img::Image;
+use crate::la::Vector2;
+use crate::la::Vector4;
+use crate::la::A;
+use crate::la::B;
+use crate::la::G;
+use crate::la::R;
+use crate::la::W;
+use crate::la::X;
+use crate::la::Y;
+use crate::obj::Obj;
+use crate::shader::transform_point;
+use crate::shader::Uniforms;
+use crate::tga::TGAColor;
+
+/// Blends `color` over the output of `inner` within `width` pixels of a triangle edge, hidden edges are removed by the depth test of the surface itself.
+pub struct WireframeShader<S: Shader> {
+    pub inner: S,
+    pub width: f32,
+    pub color: TGAColor,
+    pub viewport: Vector2<f32>,
+    varying_clip: [Vector4<f32>; 3],
+}
+
+impl<S: Shader> WireframeShader<S> {
+    pub fn new(inner: S, width: f32, color: TGAColor, viewport: Vector2<f32>) -> Self {
+        Self {
+            inner,
+            width,
+            color,
+            viewport,
+            varying_clip: [0.0.into(); 3],
+        }
+    }
+
+    /// Screen space distance in pixels from the fragment to the closest edge of the current triangle.
+    fn edge_distance(&self, fragment: &Fragment) -> Option<f32> {
+        if self.varying_clip.iter().any(|&v| 1e-6 > v.w()) {
+            return None;
+        }
+
+        let screen = self.varying_clip.map(|v| Vector2::<f32>::from([(v.x() / v.w() + 1.0) * 0.5 * self.viewport.x(), (v.y() / v.w() + 1.0) * 0.5 * self.viewport.y()]));
+        let (px, py) = (fragment.x as f32 + 0.5, fragment.y as f32 + 0.5);
+
+        let distance = (0..3)
+            .map(|i| {
+                let a = screen[i];
+                let b = screen[(i + 1) % 3];
+                let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+                let length = (dx * dx + dy * dy).sqrt();
+
+                if f32::EPSILON > length {
+                    ((px - a.x()).powi(2) + (py - a.y()).powi(2)).sqrt()
+                } else {
+                    (dx * (py - a.y()) - dy * (px - a.x())).abs() / length
+                }
+            })
+            .fold(f32::MAX, f32::min);
+
+        Some(distance)
+    }
+}
+
+impl<S: Shader> Shader for WireframeShader<S> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        let clip = self.inner.vertex(face, nthvert);
+        self.varying_clip[nthvert] = clip;
+        clip
+    }
+
+    fn fragment(&mut self, fragment: &Fragment) -> Option<TGAColor> {
+        let color = self.inner.fragment(fragment)?;
+
+        let Some(distance) = self.edge_distance(fragment) else {
+            return Some(color);
+        };
+
+        let coverage = (self.width * 0.5 + 0.5 - distance).clamp(0.0, 1.0) * self.color.a() as f32 / 255.0;
+        if 0.0 >= coverage {
+            return Some(color);
+        }
+
+        let mix = |s: u8, d: u8| (s as f32 * coverage + d as f32 * (1.0 - coverage) + 0.5) as u8;
+
+        Some([mix(self.color.r(), color.r()), mix(self.color.g(), color.g()), mix(self.color.b(), color.b()), color.a().max(self.color.a())].into())
+    }
+}
+
+/// Triangle edges drawn as lines after the shaded pass, `style.depth_bias` acts as the polygon offset keeping visible edges in front of their own faces.
+#[derive(Clone, Copy, Debug)]
+pub struct Wireframe {
+    pub style: LineStyle,
+    /// Tests the lines against the depth buffer, otherwise every edge is drawn.
+    pub hidden_line_removal: bool,
+}
+
+impl Default for Wireframe {
+    fn default() -> Self {
+        Self {
+            style: LineStyle {
+                depth_bias: 1e-3,
+                ..LineStyle::default()
+            },
+            hidden_line_removal: true,
+        }
+    }
+}
+
+impl Wireframe {
+    /// Every edge shared by several faces is drawn once.
+    pub fn draw<I: Image>(&self, image: &mut I, depth: &DepthBuffer, obj: &Obj, uniforms: &Uniforms) {
+        let mut drawn = HashSet::new();
+
+        for face in 0..obj.fs.len() {
+            for nthvert in 0..3 {
+                let a = obj.fs[face].vs[nthvert].vi;
+                let b = obj.fs[face].vs[(nthvert + 1) % 3].vi;
+
+                if !drawn.insert((a.min(b), a.max(b))) {
+                    continue;
+                }
+
+                let clip = [obj.vs[a - 1], obj.vs[b - 1]].map(|p| embed(transform_point(p, &uniforms.model), 1.0) * uniforms.view_projection);
+                line(&clip, image, self.hidden_line_removal.then_some(depth), &self.style);
+            }
+        }
+    }
+
+    /// Wireframe without a shaded pass, with hidden line removal the faces are first written to a private depth buffer only.
+    pub fn render<I: Image>(&self, image: &mut I, obj: &Obj, uniforms: &Uniforms) {
+        let mut depth = DepthBuffer::new(image.get_width(), image.get_height(), f32::MAX);
+
+        if self.hidden_line_removal {
+            let state = RenderState {
+                color_write: false,
+                ..RenderState::default()
+            };
+
+            let mut shader = DepthShader { obj, uniforms };
+            draw(obj.fs.len(), &mut shader, image, &mut depth, &state);
+        }
+
+        self.draw(image, &depth, obj, uniforms);
+    }
+}
+
+struct DepthShader<'a> {
+    obj: &'a Obj,
+    uniforms: &'a Uniforms,
+}
+
+impl Shader for DepthShader<'_> {
+    fn vertex(&mut self, face: usize, nthvert: usize) -> Vector4<f32> {
+        embed(transform_point(self.obj.vert(face, nthvert), &self.uniforms.model), 1.0) * self.uniforms.view_projection
+    }
+
+    fn fragment(&mut self, _: &Fragment) -> Option<TGAColor> {
+        Some([0, 0, 0, 0].into())
+    }
+}